@@ -17,9 +17,9 @@ struct MyError {
     message: String,
 }
 
-impl Into<axum::Error> for MyError {
-    fn into(self) -> axum::Error {
-        axum::Error::new(self.message)
+impl From<MyError> for axum::Error {
+    fn from(err: MyError) -> axum::Error {
+        axum::Error::new(err.message)
     }
 }
 
@@ -54,6 +54,16 @@ async fn test_json_nl_stream() -> impl IntoResponse {
     StreamBodyAs::json_nl_with_errors(source_test_stream())
 }
 
+async fn test_json_array_error_records_stream() -> impl IntoResponse {
+    StreamBodyAs::new(
+        JsonArrayStreamFormat::new().with_error_records(
+            JsonErrorRecordMode::Continue,
+            |err| serde_json::json!({ "error": err.to_string() }),
+        ),
+        source_test_stream(),
+    )
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     tracing_subscriber::fmt().with_target(false).init();
@@ -62,7 +72,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let app = Router::new()
         // `GET /` goes to `root`
         .route("/json-array-stream", get(test_json_array_stream))
-        .route("/json-nl-stream", get(test_json_nl_stream));
+        .route("/json-nl-stream", get(test_json_nl_stream))
+        .route(
+            "/json-array-error-records-stream",
+            get(test_json_array_error_records_stream),
+        );
 
     let listener = TcpListener::bind("127.0.0.1:8080").await?;
 
@@ -0,0 +1,55 @@
+use axum::response::IntoResponse;
+use axum::routing::*;
+use axum::Router;
+
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+
+use axum_streams::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MyTestStructure {
+    some_test_field: String,
+}
+
+fn source_test_stream() -> impl Stream<Item = MyTestStructure> {
+    // Simulating a stream with a plain vector and throttling to show how it works
+    tokio_stream::StreamExt::throttle(
+        stream::iter(vec![
+            MyTestStructure {
+                some_test_field: "test1".to_string()
+            };
+            100
+        ]),
+        std::time::Duration::from_millis(500),
+    )
+}
+
+async fn test_sse_stream() -> impl IntoResponse {
+    StreamBodyAs::sse(source_test_stream())
+}
+
+async fn test_sse_events_stream() -> impl IntoResponse {
+    StreamBodyAs::new(
+        SseStreamFormat::with_mapper(|(idx, item): (usize, MyTestStructure)| {
+            Ok(SseEvent::json(&item)?
+                .with_id(idx.to_string())
+                .with_event("my-event"))
+        }),
+        source_test_stream().enumerate().map(Ok::<_, axum::Error>),
+    )
+}
+
+#[tokio::main]
+async fn main() {
+    // build our application with a route
+    let app = Router::new()
+        // `GET /` goes to `root`
+        .route("/sse-stream", get(test_sse_stream))
+        .route("/sse-events-stream", get(test_sse_events_stream));
+
+    let listener = TcpListener::bind("127.0.0.1:8080").await.unwrap();
+
+    axum::serve(listener, app).await.unwrap();
+}
@@ -1,10 +1,13 @@
 use crate::stream_body_as::StreamBodyAsOptions;
-use crate::{StreamBodyAs, StreamingFormat};
+use crate::stream_body_from::StreamBodyFromOptions;
+use crate::{StreamBodyAs, StreamBodyFrom, StreamingDecodeFormat, StreamingFormat};
 use arrow::array::RecordBatch;
+use arrow::buffer::Buffer;
 use arrow::datatypes::{Schema, SchemaRef};
 use arrow::error::ArrowError;
+use arrow::ipc::reader::StreamDecoder;
 use arrow::ipc::writer::{
-    write_message, CompressionContext, DictionaryTracker, IpcDataGenerator, IpcWriteOptions,
+    write_message, DictionaryTracker, IpcDataGenerator, IpcWriteContext, IpcWriteOptions,
 };
 use bytes::{BufMut, BytesMut};
 use futures::stream::BoxStream;
@@ -36,12 +39,12 @@ impl StreamingFormat<RecordBatch> for ArrowRecordBatchIpcStreamFormat {
     fn to_bytes_stream<'a, 'b>(
         &'a self,
         stream: BoxStream<'b, Result<RecordBatch, axum::Error>>,
-        _: &'a StreamBodyAsOptions,
+        options: &'a StreamBodyAsOptions,
     ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
         fn write_batch(
             ipc_data_gen: &mut IpcDataGenerator,
             dictionary_tracker: &mut DictionaryTracker,
-            compression_context: &mut CompressionContext,
+            compression_context: &mut IpcWriteContext,
             write_options: &IpcWriteOptions,
             batch: &RecordBatch,
             prepend_schema: Option<Arc<Schema>>,
@@ -88,7 +91,7 @@ impl StreamingFormat<RecordBatch> for ArrowRecordBatchIpcStreamFormat {
 
         let ipc_data_gen = IpcDataGenerator::default();
         let dictionary_tracker: DictionaryTracker = DictionaryTracker::new(false);
-        let compression_context = CompressionContext::default();
+        let compression_context = IpcWriteContext::default();
 
         let batch_stream = Box::pin({
             stream.scan(
@@ -97,12 +100,12 @@ impl StreamingFormat<RecordBatch> for ArrowRecordBatchIpcStreamFormat {
                     match batch_res {
                         Err(e) => futures::future::ready(Some(Err(e))),
                         Ok(batch) => futures::future::ready({
+                            // The schema is written with the first batch written successfully
                             let prepend_schema = if *idx == 0 {
                                 Some(batch_schema.clone())
                             } else {
                                 None
                             };
-                            *idx += 1;
                             let bytes = write_batch(
                                 ipc_data_gen,
                                 dictionary_tracker,
@@ -112,6 +115,9 @@ impl StreamingFormat<RecordBatch> for ArrowRecordBatchIpcStreamFormat {
                                 prepend_schema,
                             )
                             .map_err(axum::Error::new);
+                            if bytes.is_ok() {
+                                *idx += 1;
+                            }
                             Some(bytes)
                         }),
                     }
@@ -124,7 +130,12 @@ impl StreamingFormat<RecordBatch> for ArrowRecordBatchIpcStreamFormat {
                 write_continuation().map_err(axum::Error::new)
             })));
 
-        Box::pin(batch_stream.chain(append_stream))
+        // The serialization errors are handled before writing the end of stream marker
+        Box::pin(
+            options
+                .error_policy_adapter(batch_stream)
+                .chain(append_stream),
+        )
     }
 
     fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
@@ -137,6 +148,150 @@ impl StreamingFormat<RecordBatch> for ArrowRecordBatchIpcStreamFormat {
         );
         Some(header_map)
     }
+
+    fn format_name(&self) -> &'static str {
+        "arrow_ipc"
+    }
+}
+
+impl StreamingDecodeFormat<RecordBatch> for ArrowRecordBatchIpcStreamFormat {
+    fn decode_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        options: &'a StreamBodyFromOptions,
+    ) -> BoxStream<'b, Result<RecordBatch, axum::Error>> {
+        ArrowIpcStreamDecoder::new(stream, Some(self.schema.clone()), options).into_stream()
+    }
+}
+
+/// Incremental Arrow IPC stream decoder reading schema, dictionary and record batch messages
+/// from a stream of bytes as soon as they are available.
+struct ArrowIpcStreamDecoder<'b> {
+    stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+    decoder: StreamDecoder,
+    buffer: Buffer,
+    expected_schema: Option<SchemaRef>,
+    schema_checked: bool,
+    pending_batch: Option<RecordBatch>,
+    max_obj_len: Option<usize>,
+    obj_len: usize,
+    finished: bool,
+}
+
+impl<'b> ArrowIpcStreamDecoder<'b> {
+    fn new(
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        expected_schema: Option<SchemaRef>,
+        options: &StreamBodyFromOptions,
+    ) -> Self {
+        Self {
+            stream,
+            decoder: StreamDecoder::new(),
+            buffer: Buffer::from_vec(Vec::<u8>::new()),
+            expected_schema,
+            schema_checked: false,
+            pending_batch: None,
+            max_obj_len: options.max_obj_len,
+            obj_len: 0,
+            finished: false,
+        }
+    }
+
+    fn into_stream(self) -> BoxStream<'b, Result<RecordBatch, axum::Error>> {
+        Box::pin(futures::stream::unfold(self, |mut decoder| async move {
+            decoder
+                .next_batch()
+                .await
+                .map(|batch_res| (batch_res, decoder))
+        }))
+    }
+
+    fn fail(&mut self, e: axum::Error) -> Option<Result<RecordBatch, axum::Error>> {
+        self.finished = true;
+        Some(Err(e))
+    }
+
+    fn check_schema(&mut self) -> Result<(), axum::Error> {
+        if self.schema_checked {
+            return Ok(());
+        }
+        if let (Some(schema), Some(expected_schema)) =
+            (self.decoder.schema(), self.expected_schema.as_ref())
+        {
+            if schema.fields() != expected_schema.fields() {
+                return Err(axum::Error::new(ArrowError::SchemaError(format!(
+                    "Unexpected schema: {:?}, expected: {:?}",
+                    schema, expected_schema
+                ))));
+            }
+        }
+        self.schema_checked = self.decoder.schema().is_some();
+        Ok(())
+    }
+
+    async fn read_schema(&mut self) -> Result<SchemaRef, axum::Error> {
+        loop {
+            if let Some(schema) = self.decoder.schema() {
+                return Ok(schema);
+            }
+            match self.next_batch().await {
+                Some(Ok(batch)) => self.pending_batch = Some(batch),
+                Some(Err(e)) => return Err(e),
+                None => {
+                    return Err(axum::Error::new(ArrowError::IpcError(
+                        "Missing schema".to_string(),
+                    )))
+                }
+            }
+        }
+    }
+
+    async fn next_batch(&mut self) -> Option<Result<RecordBatch, axum::Error>> {
+        if let Some(batch) = self.pending_batch.take() {
+            return Some(Ok(batch));
+        }
+        while !self.finished {
+            if self.buffer.is_empty() {
+                match self.stream.next().await {
+                    Some(Ok(bytes)) => {
+                        self.obj_len += bytes.len();
+                        self.buffer = Buffer::from(bytes);
+                    }
+                    Some(Err(e)) => return self.fail(e),
+                    None => {
+                        self.finished = true;
+                        return self
+                            .decoder
+                            .finish()
+                            .err()
+                            .map(|e| Err(axum::Error::new(e)));
+                    }
+                }
+            }
+
+            let decode_res = self.decoder.decode(&mut self.buffer);
+            if let Err(e) = self.check_schema() {
+                return self.fail(e);
+            }
+            match decode_res {
+                Ok(Some(batch)) => {
+                    self.obj_len = self.buffer.len();
+                    return Some(Ok(batch));
+                }
+                Ok(None) => match self.max_obj_len {
+                    Some(max_obj_len) if self.obj_len > max_obj_len => {
+                        return self.fail(axum::Error::new(ArrowError::IpcError(format!(
+                            "Message length exceeds {}",
+                            max_obj_len
+                        ))));
+                    }
+                    _ => {}
+                },
+                Err(e) => return self.fail(axum::Error::new(e)),
+            }
+        }
+        None
+    }
 }
 
 impl<'a> crate::StreamBodyAs<'a> {
@@ -238,6 +393,35 @@ impl StreamBodyAsOptions {
     }
 }
 
+impl StreamBodyFrom {
+    pub fn arrow_ipc(self) -> BoxStream<'static, Result<RecordBatch, axum::Error>> {
+        let (body, options) = self.into_parts();
+        ArrowIpcStreamDecoder::new(Box::pin(body.into_data_stream()), None, &options).into_stream()
+    }
+
+    /// Read the schema message first and return it with the stream of the remaining record batches.
+    /// If `expected_schema` is specified, the received schema is validated against it.
+    pub async fn arrow_ipc_with_schema(
+        self,
+        expected_schema: Option<SchemaRef>,
+    ) -> Result<
+        (
+            SchemaRef,
+            BoxStream<'static, Result<RecordBatch, axum::Error>>,
+        ),
+        axum::Error,
+    > {
+        let (body, options) = self.into_parts();
+        let mut decoder = ArrowIpcStreamDecoder::new(
+            Box::pin(body.into_data_stream()),
+            expected_schema,
+            &options,
+        );
+        let schema = decoder.read_schema().await?;
+        Ok((schema, decoder.into_stream()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -246,7 +430,7 @@ mod tests {
     use arrow::array::*;
     use arrow::datatypes::*;
     use axum::{routing::*, Router};
-    use futures::stream;
+    use futures::{stream, TryStreamExt};
     use std::sync::Arc;
 
     #[tokio::test]
@@ -312,4 +496,80 @@ mod tests {
         assert_eq!(body.len(), expected_buf.len());
         assert_eq!(body, expected_buf);
     }
+
+    fn create_test_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("city", DataType::Utf8, false),
+        ]))
+    }
+
+    fn create_test_ipc_stream(schema: &SchemaRef) -> Vec<u8> {
+        let mut writer =
+            arrow::ipc::writer::StreamWriter::try_new(Vec::new(), schema).expect("writer failed");
+        for idx in 0i64..10i64 {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(vec![idx, idx * 2, idx * 3])),
+                    Arc::new(StringArray::from(vec!["New York", "London", "Gothenburg"])),
+                ],
+            )
+            .unwrap();
+            writer.write(&batch).expect("write failed");
+        }
+        writer.finish().expect("writer failed");
+        writer.into_inner().expect("writer failed")
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_stream_format() {
+        let schema = create_test_schema();
+        let app_schema = schema.clone();
+
+        let app = Router::new().route(
+            "/",
+            post(|body: StreamBodyFrom| async move {
+                let (received_schema, batches) =
+                    body.arrow_ipc_with_schema(Some(app_schema)).await.unwrap();
+                let batches: Vec<RecordBatch> = batches.try_collect().await.unwrap();
+                StreamBodyAs::arrow_ipc(received_schema, stream::iter(batches))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let request_body = create_test_ipc_stream(&schema);
+
+        let request_chunks: Vec<Result<Vec<u8>, std::io::Error>> = request_body
+            .chunks(13)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect();
+
+        let res = client
+            .post("/")
+            .body(reqwest::Body::wrap_stream(stream::iter(request_chunks)))
+            .send()
+            .await
+            .unwrap();
+        let body = res.bytes().await.unwrap().to_vec();
+
+        assert_eq!(body, request_body);
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_stream_format_unexpected_schema() {
+        let schema = create_test_schema();
+        let expected_schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Utf8, false)]));
+
+        let body = StreamBodyFrom::new(axum::body::Body::from(create_test_ipc_stream(&schema)));
+
+        let batches: Vec<Result<RecordBatch, axum::Error>> = body
+            .decode(ArrowRecordBatchIpcStreamFormat::new(expected_schema))
+            .collect()
+            .await;
+
+        assert_eq!(batches.len(), 1);
+        assert!(batches[0].is_err());
+    }
 }
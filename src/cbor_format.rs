@@ -0,0 +1,175 @@
+use crate::stream_body_as::StreamBodyAsOptions;
+use crate::stream_format::StreamingFormat;
+use crate::StreamBodyAs;
+use futures::stream::BoxStream;
+use futures::Stream;
+use futures::StreamExt;
+use http::HeaderMap;
+use serde::Serialize;
+
+/// CBOR sequence format ([RFC 8742](https://www.rfc-editor.org/rfc/rfc8742)):
+/// a concatenation of CBOR data items without any framing.
+pub struct CborSeqStreamFormat;
+
+impl CborSeqStreamFormat {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<T> StreamingFormat<T> for CborSeqStreamFormat
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    fn to_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<T, axum::Error>>,
+        options: &'a StreamBodyAsOptions,
+    ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
+        Box::pin({
+            options.error_policy_adapter(stream.map(|obj_res| match obj_res {
+                Err(e) => Err(e),
+                Ok(obj) => {
+                    let mut buf = Vec::new();
+                    ciborium::into_writer(&obj, &mut buf)
+                        .map(|_| axum::body::Bytes::from(buf))
+                        .map_err(axum::Error::new)
+                }
+            }))
+        })
+    }
+
+    fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
+        let mut header_map = HeaderMap::new();
+        header_map.insert(
+            http::header::CONTENT_TYPE,
+            options
+                .content_type
+                .clone()
+                .unwrap_or_else(|| http::header::HeaderValue::from_static("application/cbor-seq")),
+        );
+        Some(header_map)
+    }
+
+    fn format_name(&self) -> &'static str {
+        "cbor_seq"
+    }
+}
+
+impl<'a> StreamBodyAs<'a> {
+    pub fn cbor_seq<S, T>(stream: S) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = T> + 'a + Send,
+    {
+        Self::new(CborSeqStreamFormat::new(), stream.map(Ok::<T, axum::Error>))
+    }
+
+    pub fn cbor_seq_with_errors<S, T, E>(stream: S) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = Result<T, E>> + 'a + Send,
+        E: Into<axum::Error>,
+    {
+        Self::new(CborSeqStreamFormat::new(), stream)
+    }
+}
+
+impl StreamBodyAsOptions {
+    pub fn cbor_seq<'a, S, T>(self, stream: S) -> StreamBodyAs<'a>
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = T> + 'a + Send,
+    {
+        StreamBodyAs::with_options(
+            CborSeqStreamFormat::new(),
+            stream.map(Ok::<T, axum::Error>),
+            self,
+        )
+    }
+
+    pub fn cbor_seq_with_errors<'a, S, T, E>(self, stream: S) -> StreamBodyAs<'a>
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = Result<T, E>> + 'a + Send,
+        E: Into<axum::Error>,
+    {
+        StreamBodyAs::with_options(CborSeqStreamFormat::new(), stream, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use crate::StreamBodyAs;
+    use axum::{routing::*, Router};
+    use futures::stream;
+    use serde::Deserialize;
+
+    #[tokio::test]
+    async fn serialize_cbor_seq_stream_format() {
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        struct TestOutputStructure {
+            foo: String,
+            bar: Vec<u32>,
+        }
+
+        let test_stream_vec: Vec<TestOutputStructure> = (0..7)
+            .map(|idx| TestOutputStructure {
+                foo: "bar".repeat(idx),
+                bar: (0..idx as u32).collect(),
+            })
+            .collect();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::cbor_seq(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap();
+        assert_eq!(
+            res.headers()
+                .get("content-type")
+                .and_then(|h| h.to_str().ok()),
+            Some("application/cbor-seq")
+        );
+        let body = res.bytes().await.unwrap();
+
+        let mut reader = body.as_ref();
+        let mut items: Vec<TestOutputStructure> = Vec::new();
+        while !reader.is_empty() {
+            items.push(ciborium::from_reader(&mut reader).unwrap());
+        }
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn serialize_cbor_seq_stream_format_with_errors() {
+        let test_stream = || stream::iter(vec![Ok(1), Ok(2), Err(axum::Error::new("test error"))]);
+
+        let app = Router::new().route(
+            "/",
+            get(move || async move {
+                StreamBodyAsOptions::new()
+                    .error_policy(crate::StreamErrorPolicy::Terminate)
+                    .cbor_seq_with_errors(test_stream())
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap();
+        let body = res.bytes().await.unwrap();
+
+        let mut reader = body.as_ref();
+        let mut items: Vec<i32> = Vec::new();
+        while !reader.is_empty() {
+            items.push(ciborium::from_reader(&mut reader).unwrap());
+        }
+
+        assert_eq!(items, vec![1, 2]);
+    }
+}
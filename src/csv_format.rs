@@ -1,11 +1,16 @@
 use crate::stream_body_as::StreamBodyAsOptions;
-use crate::stream_format::StreamingFormat;
-use crate::StreamBodyAs;
+use crate::stream_body_from::StreamBodyFromOptions;
+use crate::stream_format::{StreamingDecodeFormat, StreamingFormat};
+use crate::{StreamBodyAs, StreamBodyFrom};
+use bytes::{Buf, BytesMut};
 use futures::stream::BoxStream;
-use futures::Stream;
 use futures::StreamExt;
+use futures::{Stream, TryStreamExt};
 use http::HeaderMap;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
+use tokio_util::codec::FramedRead;
+use tokio_util::io::StreamReader;
 
 pub struct CsvStreamFormat {
     has_headers: bool,
@@ -98,7 +103,7 @@ where
     fn to_bytes_stream<'a, 'b>(
         &'a self,
         stream: BoxStream<'b, Result<T, axum::Error>>,
-        _: &'a StreamBodyAsOptions,
+        options: &'a StreamBodyAsOptions,
     ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
         let stream_with_header = self.has_headers;
         let stream_delimiter = self.delimiter;
@@ -109,31 +114,34 @@ where
         let stream_escape = self.escape;
         let terminator = self.terminator;
 
+        // The header is written with the first record serialized successfully,
+        // so it is not lost with the skipped errors
+        let mut header_written = false;
         Box::pin({
-            stream
-                .enumerate()
-                .map(move |(index, obj_res)| match obj_res {
-                    Err(e) => Err(e),
-                    Ok(obj) => {
-                        let mut writer = csv::WriterBuilder::new()
-                            .has_headers(index == 0 && stream_with_header)
-                            .delimiter(stream_delimiter)
-                            .flexible(stream_flexible)
-                            .quote_style(stream_quote_style)
-                            .quote(stream_quote)
-                            .double_quote(stream_double_quote)
-                            .escape(stream_escape)
-                            .terminator(terminator)
-                            .from_writer(vec![]);
-
-                        writer.serialize(obj).map_err(axum::Error::new)?;
-                        writer.flush().map_err(axum::Error::new)?;
-                        writer
-                            .into_inner()
-                            .map_err(axum::Error::new)
-                            .map(axum::body::Bytes::from)
-                    }
-                })
+            options.error_policy_adapter(stream.map(move |obj_res| match obj_res {
+                Err(e) => Err(e),
+                Ok(obj) => {
+                    let mut writer = csv::WriterBuilder::new()
+                        .has_headers(!header_written && stream_with_header)
+                        .delimiter(stream_delimiter)
+                        .flexible(stream_flexible)
+                        .quote_style(stream_quote_style)
+                        .quote(stream_quote)
+                        .double_quote(stream_double_quote)
+                        .escape(stream_escape)
+                        .terminator(terminator)
+                        .from_writer(vec![]);
+
+                    writer.serialize(obj).map_err(axum::Error::new)?;
+                    writer.flush().map_err(axum::Error::new)?;
+                    let bytes = writer
+                        .into_inner()
+                        .map_err(axum::Error::new)
+                        .map(axum::body::Bytes::from)?;
+                    header_written = true;
+                    Ok(bytes)
+                }
+            }))
         })
     }
 
@@ -148,6 +156,251 @@ where
         );
         Some(header_map)
     }
+
+    fn format_name(&self) -> &'static str {
+        "csv"
+    }
+}
+
+impl<T> StreamingDecodeFormat<T> for CsvStreamFormat
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    fn decode_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        options: &'a StreamBodyFromOptions,
+    ) -> BoxStream<'b, Result<T, axum::Error>> {
+        let stream_with_header = self.has_headers;
+        let reader = StreamReader::new(
+            stream.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+        );
+        let codec = CsvCodec::new(self, options.max_obj_len);
+        let mut headers: Option<csv::ByteRecord> = None;
+
+        Box::pin({
+            FramedRead::new(reader, codec).filter_map(move |record_res| {
+                futures::future::ready(match record_res.and_then(|record| record) {
+                    Err(e) => Some(Err(axum::Error::new(e))),
+                    Ok(record) if stream_with_header && headers.is_none() => {
+                        headers = Some(record);
+                        None
+                    }
+                    Ok(record) => Some(
+                        record
+                            .deserialize(headers.as_ref())
+                            .map_err(axum::Error::new),
+                    ),
+                })
+            })
+        })
+    }
+}
+
+/// Incremental CSV records decoder on top of `csv_core` reader
+/// keeping track of the records positions for error reporting.
+/// Invalid records are reported as the errors of the individual records,
+/// and the decoding continues from the next record.
+struct CsvCodec {
+    reader: csv_core::Reader,
+    flexible: bool,
+    max_obj_len: Option<usize>,
+    output: Vec<u8>,
+    output_len: usize,
+    ends: Vec<usize>,
+    ends_len: usize,
+    record_len: usize,
+    record_position: Option<csv::Position>,
+    byte: u64,
+    record: u64,
+    first_record_len: Option<usize>,
+    skipping_record: bool,
+}
+
+impl CsvCodec {
+    fn new(format: &CsvStreamFormat, max_obj_len: Option<usize>) -> Self {
+        let terminator = match format.terminator {
+            csv::Terminator::Any(terminator) => csv_core::Terminator::Any(terminator),
+            _ => csv_core::Terminator::CRLF,
+        };
+        let reader = csv_core::ReaderBuilder::new()
+            .delimiter(format.delimiter)
+            .quote(format.quote)
+            .quoting(!matches!(format.quote_style, csv::QuoteStyle::Never))
+            .double_quote(format.double_quote)
+            .escape(if format.double_quote {
+                None
+            } else {
+                Some(format.escape)
+            })
+            .terminator(terminator)
+            .build();
+
+        Self {
+            reader,
+            flexible: format.flexible,
+            max_obj_len,
+            output: vec![0; 1024],
+            output_len: 0,
+            ends: vec![0; 16],
+            ends_len: 0,
+            record_len: 0,
+            record_position: None,
+            byte: 0,
+            record: 0,
+            first_record_len: None,
+            skipping_record: false,
+        }
+    }
+
+    fn invalid_data(position: &csv::Position, message: String) -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "CSV error: record {} (line: {}, byte: {}): {}",
+                position.record(),
+                position.line(),
+                position.byte(),
+                message
+            ),
+        )
+    }
+
+    fn skip_record(&mut self) {
+        self.skipping_record = false;
+        self.record_position = None;
+        self.output_len = 0;
+        self.ends_len = 0;
+        self.record_len = 0;
+        self.record += 1;
+    }
+
+    fn read_record(
+        &mut self,
+        input: &[u8],
+    ) -> (usize, Option<Result<csv::ByteRecord, std::io::Error>>) {
+        let mut consumed = 0;
+        loop {
+            let position = self.record_position.get_or_insert_with(|| {
+                let mut position = csv::Position::new();
+                position
+                    .set_byte(self.byte)
+                    .set_line(self.reader.line())
+                    .set_record(self.record);
+                position
+            });
+
+            let (result, nin, nout, nend) = self.reader.read_record(
+                &input[consumed..],
+                &mut self.output[self.output_len..],
+                &mut self.ends[self.ends_len..],
+            );
+            consumed += nin;
+            self.byte += nin as u64;
+            self.record_len += nin;
+            self.output_len += nout;
+            self.ends_len += nend;
+
+            if let Some(max_obj_len) = self.max_obj_len {
+                if !self.skipping_record && self.record_len > max_obj_len {
+                    let error = Self::invalid_data(
+                        position,
+                        format!("Record length exceeds {}", max_obj_len),
+                    );
+                    // The rest of the record is skipped without buffering
+                    if matches!(result, csv_core::ReadRecordResult::Record) {
+                        self.skip_record();
+                    } else {
+                        self.skipping_record = true;
+                    }
+                    return (consumed, Some(Err(error)));
+                }
+            }
+
+            match result {
+                csv_core::ReadRecordResult::InputEmpty | csv_core::ReadRecordResult::End => {
+                    return (consumed, None);
+                }
+                csv_core::ReadRecordResult::OutputFull if self.skipping_record => {
+                    self.output_len = 0;
+                }
+                csv_core::ReadRecordResult::OutputEndsFull if self.skipping_record => {
+                    self.ends_len = 0;
+                }
+                csv_core::ReadRecordResult::OutputFull => {
+                    self.output.resize(self.output.len() * 2, 0);
+                }
+                csv_core::ReadRecordResult::OutputEndsFull => {
+                    self.ends.resize(self.ends.len() * 2, 0);
+                }
+                csv_core::ReadRecordResult::Record if self.skipping_record => {
+                    self.skip_record();
+                }
+                csv_core::ReadRecordResult::Record => {
+                    let mut record = csv::ByteRecord::with_capacity(self.output_len, self.ends_len);
+                    let mut field_start = 0;
+                    for field_end in &self.ends[..self.ends_len] {
+                        record.push_field(&self.output[field_start..*field_end]);
+                        field_start = *field_end;
+                    }
+
+                    let position = self
+                        .record_position
+                        .take()
+                        .unwrap_or_else(csv::Position::new);
+                    self.output_len = 0;
+                    self.ends_len = 0;
+                    self.record_len = 0;
+                    self.record += 1;
+
+                    match self.first_record_len {
+                        Some(first_record_len)
+                            if !self.flexible && first_record_len != record.len() =>
+                        {
+                            return (
+                                consumed,
+                                Some(Err(Self::invalid_data(
+                                    &position,
+                                    format!(
+                                        "found record with {} fields, but the previous record has {} fields",
+                                        record.len(),
+                                        first_record_len
+                                    ),
+                                ))),
+                            );
+                        }
+                        Some(_) => {}
+                        None => self.first_record_len = Some(record.len()),
+                    }
+
+                    record.set_position(Some(position));
+                    return (consumed, Some(Ok(record)));
+                }
+            }
+        }
+    }
+}
+
+impl tokio_util::codec::Decoder for CsvCodec {
+    type Item = Result<csv::ByteRecord, std::io::Error>;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        // Empty input means the end of data for `csv_core` reader
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let (consumed, record) = self.read_record(src);
+        src.advance(consumed);
+        Ok(record)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(record) => Ok(Some(record)),
+            None => Ok(self.read_record(&[]).1),
+        }
+    }
 }
 
 impl<'a> StreamBodyAs<'a> {
@@ -192,6 +445,18 @@ impl StreamBodyAsOptions {
     }
 }
 
+impl StreamBodyFrom {
+    /// Decode the body as CSV records with the header.
+    /// The invalid records (e.g. with a different number of fields or longer than `max_obj_len`)
+    /// are reported as errors, and the decoding continues from the next record.
+    pub fn csv<T>(self) -> BoxStream<'static, Result<T, axum::Error>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.decode(CsvStreamFormat::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,6 +464,7 @@ mod tests {
     use crate::StreamBodyAs;
     use axum::{routing::*, Router};
     use futures::stream;
+    use serde::Deserialize;
     use std::ops::Add;
 
     #[tokio::test]
@@ -249,4 +515,163 @@ mod tests {
 
         assert_eq!(body, expected_csv);
     }
+
+    #[tokio::test]
+    async fn serialize_csv_stream_format_with_serialization_errors() {
+        // Fails to serialize the negative numbers
+        struct TestOutputStructure(i32);
+
+        impl Serialize for TestOutputStructure {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeStruct;
+                if self.0 < 0 {
+                    return Err(serde::ser::Error::custom("negative number"));
+                }
+                let mut state = serializer.serialize_struct("TestOutputStructure", 1)?;
+                state.serialize_field("foo", &self.0)?;
+                state.end()
+            }
+        }
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                StreamBodyAs::with_options(
+                    CsvStreamFormat::default(),
+                    stream::iter(vec![-1, 1, -2, 2].into_iter().map(TestOutputStructure))
+                        .map(Ok::<_, axum::Error>),
+                    StreamBodyAsOptions::new().error_policy(crate::StreamErrorPolicy::skip(|_| {})),
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        // The header is written with the first record serialized
+        let res = client.get("/").send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "foo\n1\n2\n");
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_format() {
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        struct TestInputStructure {
+            foo1: String,
+            foo2: String,
+        }
+
+        let test_stream_vec = vec![
+            TestInputStructure {
+                foo1: "bar1".to_string(),
+                foo2: "bar2;\"quoted\"\nmultiline".to_string()
+            };
+            7
+        ];
+
+        let app = Router::new().route(
+            "/",
+            post(|body: StreamBodyFrom| async {
+                let items: Vec<TestInputStructure> = body
+                    .decode(CsvStreamFormat::new(true, b';'))
+                    .try_collect()
+                    .await
+                    .unwrap();
+                axum::Json(items)
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        // Columns in a different order to check mapping by headers
+        let request_body = "foo2;foo1\n".to_string()
+            + &test_stream_vec
+                .iter()
+                .map(|item| format!("\"{}\";{}\n", item.foo2.replace('"', "\"\""), item.foo1))
+                .collect::<String>();
+
+        let request_chunks: Vec<Result<Vec<u8>, std::io::Error>> = request_body
+            .as_bytes()
+            .chunks(5)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect();
+
+        let res = client
+            .post("/")
+            .body(reqwest::Body::wrap_stream(stream::iter(request_chunks)))
+            .send()
+            .await
+            .unwrap();
+
+        let items: Vec<TestInputStructure> = res.json().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_format_errors_position() {
+        #[derive(Debug, Clone, PartialEq, Deserialize)]
+        struct TestInputStructure {
+            foo1: String,
+            foo2: i32,
+        }
+
+        let body = StreamBodyFrom::new(axum::body::Body::from(
+            "foo1,foo2\nbar1,1\nbar2,not_a_number\nbar3\n",
+        ));
+
+        let items: Vec<Result<TestInputStructure, axum::Error>> = body.csv().collect().await;
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[0].as_ref().unwrap(),
+            &TestInputStructure {
+                foo1: "bar1".to_string(),
+                foo2: 1
+            }
+        );
+        let error = items[1].as_ref().unwrap_err().to_string();
+        assert!(error.contains("record 2 (line: 3, byte: 17)"), "{}", error);
+        let error = items[2].as_ref().unwrap_err().to_string();
+        assert!(error.contains("record 3 (line: 4, byte: 35)"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_format_resync_after_errors() {
+        #[derive(Debug, Clone, PartialEq, Deserialize)]
+        struct TestInputStructure {
+            foo1: String,
+            foo2: i32,
+        }
+
+        let request_body = format!("foo1,foo2\nbar1,1\nbar2\n{},2\nbar3,3\n", "x".repeat(64));
+        let request_chunks: Vec<Result<axum::body::Bytes, std::io::Error>> = request_body
+            .as_bytes()
+            .chunks(5)
+            .map(|chunk| Ok(axum::body::Bytes::copy_from_slice(chunk)))
+            .collect();
+        let body = StreamBodyFrom::new(axum::body::Body::from_stream(stream::iter(request_chunks)))
+            .with_options(StreamBodyFromOptions::new().max_obj_len(32));
+
+        let items: Vec<Result<TestInputStructure, axum::Error>> = body.csv().collect().await;
+
+        assert_eq!(items.len(), 4);
+        assert_eq!(
+            items[0].as_ref().unwrap(),
+            &TestInputStructure {
+                foo1: "bar1".to_string(),
+                foo2: 1
+            }
+        );
+        let error = items[1].as_ref().unwrap_err().to_string();
+        assert!(error.contains("record 2 (line: 3"), "{}", error);
+        let error = items[2].as_ref().unwrap_err().to_string();
+        assert!(error.contains("Record length exceeds 32"), "{}", error);
+        assert_eq!(
+            items[3].as_ref().unwrap(),
+            &TestInputStructure {
+                foo1: "bar3".to_string(),
+                foo2: 3
+            }
+        );
+    }
 }
@@ -1,24 +1,34 @@
 use crate::stream_body_as::StreamBodyAsOptions;
-use crate::stream_format::StreamingFormat;
-use crate::{StreamBodyAs, StreamFormatEnvelope};
-use bytes::{BufMut, BytesMut};
+use crate::stream_body_from::StreamBodyFromOptions;
+use crate::stream_format::{StreamingDecodeFormat, StreamingFormat};
+use crate::{StreamBodyAs, StreamBodyFrom, StreamFormatEnvelope};
+use bytes::{Buf, BufMut, BytesMut};
+use futures::channel::oneshot;
 use futures::stream::BoxStream;
-use futures::Stream;
 use futures::StreamExt;
+use futures::{Stream, TryStreamExt};
 use http::HeaderMap;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::io::Write;
+use std::sync::Arc;
+use tokio_util::codec::{AnyDelimiterCodec, FramedRead, LinesCodec};
+use tokio_util::io::StreamReader;
 
 pub struct JsonArrayStreamFormat<E = ()>
 where
     E: Serialize,
 {
     envelope: Option<StreamFormatEnvelope<E>>,
+    error_records: Option<JsonErrorRecords>,
 }
 
 impl JsonArrayStreamFormat {
     pub fn new() -> JsonArrayStreamFormat<()> {
-        JsonArrayStreamFormat { envelope: None }
+        JsonArrayStreamFormat {
+            envelope: None,
+            error_records: None,
+        }
     }
 
     pub fn with_envelope<E>(envelope: E, array_field: &str) -> JsonArrayStreamFormat<E>
@@ -30,10 +40,29 @@ impl JsonArrayStreamFormat {
                 object: envelope,
                 array_field: array_field.to_string(),
             }),
+            error_records: None,
         }
     }
 }
 
+impl<E> JsonArrayStreamFormat<E>
+where
+    E: Serialize,
+{
+    /// Write the errors from the stream as the array items mapped with the specified function,
+    /// instead of aborting the response, so the clients always receive a valid JSON.
+    pub fn with_error_records<F>(mut self, mode: JsonErrorRecordMode, mapper: F) -> Self
+    where
+        F: Fn(&axum::Error) -> serde_json::Value + Send + Sync + 'static,
+    {
+        self.error_records = Some(JsonErrorRecords {
+            mode,
+            mapper: Arc::new(mapper),
+        });
+        self
+    }
+}
+
 impl<T, E> StreamingFormat<T> for JsonArrayStreamFormat<E>
 where
     T: Serialize + Send + Sync + 'static,
@@ -42,31 +71,29 @@ where
     fn to_bytes_stream<'a, 'b>(
         &'a self,
         stream: BoxStream<'b, Result<T, axum::Error>>,
-        _: &'a StreamBodyAsOptions,
+        options: &'a StreamBodyAsOptions,
     ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
-        let stream_bytes: BoxStream<Result<axum::body::Bytes, axum::Error>> = Box::pin({
-            stream.enumerate().map(|(index, obj_res)| match obj_res {
-                Err(e) => Err(e),
-                Ok(obj) => {
-                    let mut buf = BytesMut::new().writer();
+        let serialized_stream: BoxStream<Result<Vec<u8>, axum::Error>> =
+            Box::pin(json_records(stream, &self.error_records).map(|obj_res| {
+                obj_res.and_then(|obj| serde_json::to_vec(&obj).map_err(axum::Error::new))
+            }));
+        // The serialization errors are handled before writing the separators,
+        // so the skipped items don't leave the dangling separators in the array
+        let serialized_stream = Box::pin(options.error_policy_adapter(serialized_stream));
 
-                    let sep_write_res = if index != 0 {
-                        buf.write_all(JSON_SEP_BYTES).map_err(axum::Error::new)
-                    } else {
-                        Ok(())
-                    };
-
-                    match sep_write_res {
-                        Ok(_) => {
-                            match serde_json::to_writer(&mut buf, &obj).map_err(axum::Error::new) {
-                                Ok(_) => Ok(buf.into_inner().freeze()),
-                                Err(e) => Err(e),
-                            }
-                        }
-                        Err(e) => Err(e),
+        let stream_bytes: BoxStream<Result<axum::body::Bytes, axum::Error>> = Box::pin({
+            serialized_stream
+                .enumerate()
+                .map(|(index, obj_res)| match obj_res {
+                    Err(e) => Err(e),
+                    Ok(obj_bytes) if index != 0 => {
+                        let mut buf = BytesMut::with_capacity(obj_bytes.len() + 1);
+                        buf.put_slice(JSON_SEP_BYTES);
+                        buf.put_slice(&obj_bytes);
+                        Ok(buf.freeze())
                     }
-                }
-            })
+                    Ok(obj_bytes) => Ok(axum::body::Bytes::from(obj_bytes)),
+                })
         });
 
         let prepend_stream: BoxStream<Result<axum::body::Bytes, axum::Error>> =
@@ -130,13 +157,80 @@ where
         );
         Some(header_map)
     }
+
+    fn format_name(&self) -> &'static str {
+        "json_array"
+    }
+
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        // Whitespaces are allowed between the array elements
+        Some(axum::body::Bytes::from_static(JSON_NL_SEP_BYTES))
+    }
+}
+
+impl<T, E> StreamingDecodeFormat<T> for JsonArrayStreamFormat<E>
+where
+    T: DeserializeOwned + Send + 'static,
+    E: Serialize,
+{
+    fn decode_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        options: &'a StreamBodyFromOptions,
+    ) -> BoxStream<'b, Result<T, axum::Error>> {
+        let array_field = self
+            .envelope
+            .as_ref()
+            .map(|envelope| envelope.array_field.as_str());
+
+        Box::pin({
+            json_array_frames(stream, array_field, options).filter_map(|frame_res| {
+                futures::future::ready(match frame_res {
+                    Err(e) => Some(Err(e)),
+                    Ok(JsonArrayFrame::Item(bytes)) => {
+                        Some(serde_json::from_slice(&bytes).map_err(axum::Error::new))
+                    }
+                    Ok(JsonArrayFrame::Envelope(_)) => None,
+                })
+            })
+        })
+    }
 }
 
 pub struct JsonNewLineStreamFormat;
 
 impl JsonNewLineStreamFormat {
     pub fn new() -> Self {
-        Self {}
+        Self
+    }
+
+    /// The media type for `Content-Type` header.
+    /// [`StreamBodyAsOptions::content_type`] still takes precedence over it.
+    pub fn with_content_type(
+        self,
+        content_type: JsonNewLineContentType,
+    ) -> JsonNewLineStreamFormatBuilder {
+        JsonNewLineStreamFormatBuilder::new().with_content_type(content_type)
+    }
+
+    pub fn with_terminator(
+        self,
+        terminator: JsonNewLineTerminator,
+    ) -> JsonNewLineStreamFormatBuilder {
+        JsonNewLineStreamFormatBuilder::new().with_terminator(terminator)
+    }
+
+    /// Write the errors from the stream as the lines mapped with the specified function,
+    /// instead of aborting the response.
+    pub fn with_error_records<F>(
+        self,
+        mode: JsonErrorRecordMode,
+        mapper: F,
+    ) -> JsonNewLineStreamFormatBuilder
+    where
+        F: Fn(&axum::Error) -> serde_json::Value + Send + Sync + 'static,
+    {
+        JsonNewLineStreamFormatBuilder::new().with_error_records(mode, mapper)
     }
 }
 
@@ -147,33 +241,633 @@ where
     fn to_bytes_stream<'a, 'b>(
         &'a self,
         stream: BoxStream<'b, Result<T, axum::Error>>,
-        _: &'a StreamBodyAsOptions,
+        options: &'a StreamBodyAsOptions,
     ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
-        Box::pin({
-            stream.map(|obj_res| match obj_res {
+        JsonNewLineStreamFormatBuilder::new().to_bytes_stream(stream, options)
+    }
+
+    fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
+        StreamingFormat::<T>::http_response_headers(&JsonNewLineStreamFormatBuilder::new(), options)
+    }
+
+    fn format_name(&self) -> &'static str {
+        "json_lines"
+    }
+
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        StreamingFormat::<T>::heartbeat_bytes(&JsonNewLineStreamFormatBuilder::new())
+    }
+}
+
+impl<T> StreamingDecodeFormat<T> for JsonNewLineStreamFormat
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    fn decode_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        options: &'a StreamBodyFromOptions,
+    ) -> BoxStream<'b, Result<T, axum::Error>> {
+        JsonNewLineStreamFormatBuilder::new().decode_bytes_stream(stream, options)
+    }
+}
+
+/// JSON Lines format with the error records, the content type or the line terminator specified,
+/// created from [`JsonNewLineStreamFormat`].
+pub struct JsonNewLineStreamFormatBuilder {
+    error_records: Option<JsonErrorRecords>,
+    content_type: JsonNewLineContentType,
+    terminator: JsonNewLineTerminator,
+}
+
+/// Media types commonly used for the JSON Lines responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonNewLineContentType {
+    /// `application/jsonstream` (default)
+    JsonStream,
+    /// `application/x-ndjson`
+    NdJson,
+    /// `application/jsonl`
+    JsonLines,
+}
+
+impl JsonNewLineContentType {
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            JsonNewLineContentType::JsonStream => "application/jsonstream",
+            JsonNewLineContentType::NdJson => "application/x-ndjson",
+            JsonNewLineContentType::JsonLines => "application/jsonl",
+        }
+    }
+}
+
+impl Default for JsonNewLineContentType {
+    fn default() -> Self {
+        JsonNewLineContentType::JsonStream
+    }
+}
+
+/// Line terminator written after each JSON object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonNewLineTerminator {
+    /// `\n` (default)
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl JsonNewLineTerminator {
+    fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            JsonNewLineTerminator::Lf => JSON_NL_SEP_BYTES,
+            JsonNewLineTerminator::CrLf => JSON_CRLF_SEP_BYTES,
+        }
+    }
+}
+
+impl Default for JsonNewLineTerminator {
+    fn default() -> Self {
+        JsonNewLineTerminator::Lf
+    }
+}
+
+impl JsonNewLineStreamFormatBuilder {
+    pub fn new() -> Self {
+        Self {
+            error_records: None,
+            content_type: JsonNewLineContentType::default(),
+            terminator: JsonNewLineTerminator::default(),
+        }
+    }
+
+    /// The media type for `Content-Type` header.
+    /// [`StreamBodyAsOptions::content_type`] still takes precedence over it.
+    pub fn with_content_type(mut self, content_type: JsonNewLineContentType) -> Self {
+        self.content_type = content_type;
+        self
+    }
+
+    pub fn with_terminator(mut self, terminator: JsonNewLineTerminator) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Write the errors from the stream as the lines mapped with the specified function,
+    /// instead of aborting the response.
+    pub fn with_error_records<F>(mut self, mode: JsonErrorRecordMode, mapper: F) -> Self
+    where
+        F: Fn(&axum::Error) -> serde_json::Value + Send + Sync + 'static,
+    {
+        self.error_records = Some(JsonErrorRecords {
+            mode,
+            mapper: Arc::new(mapper),
+        });
+        self
+    }
+}
+
+impl<T> StreamingFormat<T> for JsonNewLineStreamFormatBuilder
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    fn to_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<T, axum::Error>>,
+        options: &'a StreamBodyAsOptions,
+    ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
+        let terminator = self.terminator.as_bytes();
+        let stream_bytes: BoxStream<Result<axum::body::Bytes, axum::Error>> = Box::pin(
+            json_records(stream, &self.error_records).map(move |obj_res| match obj_res {
                 Err(e) => Err(e),
                 Ok(obj) => {
                     let mut buf = BytesMut::new().writer();
                     match serde_json::to_writer(&mut buf, &obj).map_err(axum::Error::new) {
-                        Ok(_) => match buf.write_all(JSON_NL_SEP_BYTES).map_err(axum::Error::new) {
+                        Ok(_) => match buf.write_all(terminator).map_err(axum::Error::new) {
                             Ok(_) => Ok(buf.into_inner().freeze()),
                             Err(e) => Err(e),
                         },
                         Err(e) => Err(e),
                     }
                 }
-            })
+            }),
+        );
+        Box::pin(options.error_policy_adapter(stream_bytes))
+    }
+
+    fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
+        let mut header_map = HeaderMap::new();
+        header_map.insert(
+            http::header::CONTENT_TYPE,
+            options.content_type.clone().unwrap_or_else(|| {
+                http::header::HeaderValue::from_static(self.content_type.media_type())
+            }),
+        );
+        Some(header_map)
+    }
+
+    fn format_name(&self) -> &'static str {
+        "json_lines"
+    }
+
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        Some(axum::body::Bytes::from_static(self.terminator.as_bytes()))
+    }
+}
+
+impl<T> StreamingDecodeFormat<T> for JsonNewLineStreamFormatBuilder
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    fn decode_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        options: &'a StreamBodyFromOptions,
+    ) -> BoxStream<'b, Result<T, axum::Error>> {
+        let reader = StreamReader::new(
+            stream.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+        );
+        let codec = options
+            .max_obj_len
+            .map(LinesCodec::new_with_max_length)
+            .unwrap_or_default();
+
+        Box::pin({
+            FramedRead::new(reader, codec)
+                .try_filter(|line| futures::future::ready(!line.trim().is_empty()))
+                .map(|line_res| match line_res {
+                    Err(e) => Err(axum::Error::new(e)),
+                    Ok(line) => serde_json::from_str(&line).map_err(axum::Error::new),
+                })
+        })
+    }
+}
+
+/// JSON Text Sequences format ([RFC 7464](https://www.rfc-editor.org/rfc/rfc7464)):
+/// each JSON text is prefixed with the record separator (0x1E) and followed by a line feed.
+pub struct JsonSeqStreamFormat;
+
+impl JsonSeqStreamFormat {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<T> StreamingFormat<T> for JsonSeqStreamFormat
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    fn to_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<T, axum::Error>>,
+        options: &'a StreamBodyAsOptions,
+    ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
+        Box::pin({
+            options.error_policy_adapter(stream.map(|obj_res| match obj_res {
+                Err(e) => Err(e),
+                Ok(obj) => {
+                    let mut buf = BytesMut::new().writer();
+                    buf.write_all(JSON_SEQ_RS_BYTES)
+                        .map_err(axum::Error::new)
+                        .and_then(|_| {
+                            serde_json::to_writer(&mut buf, &obj).map_err(axum::Error::new)
+                        })
+                        .and_then(|_| buf.write_all(JSON_NL_SEP_BYTES).map_err(axum::Error::new))
+                        .map(|_| buf.into_inner().freeze())
+                }
+            }))
         })
     }
 
-    fn http_response_headers(&self, _: &StreamBodyAsOptions) -> Option<HeaderMap> {
+    fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
         let mut header_map = HeaderMap::new();
         header_map.insert(
             http::header::CONTENT_TYPE,
-            http::header::HeaderValue::from_static("application/jsonstream"),
+            options
+                .content_type
+                .clone()
+                .unwrap_or_else(|| http::header::HeaderValue::from_static("application/json-seq")),
         );
         Some(header_map)
     }
+
+    fn format_name(&self) -> &'static str {
+        "json_seq"
+    }
+
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        // Whitespaces after the line feed are ignored as a part of the previous record
+        Some(axum::body::Bytes::from_static(JSON_NL_SEP_BYTES))
+    }
+}
+
+impl<T> StreamingDecodeFormat<T> for JsonSeqStreamFormat
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    fn decode_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        options: &'a StreamBodyFromOptions,
+    ) -> BoxStream<'b, Result<T, axum::Error>> {
+        let reader = StreamReader::new(
+            stream.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+        );
+        let codec = AnyDelimiterCodec::new_with_max_length(
+            JSON_SEQ_RS_BYTES.to_vec(),
+            Vec::new(),
+            options.max_obj_len.unwrap_or(usize::MAX),
+        );
+
+        Box::pin({
+            FramedRead::new(reader, codec).filter_map(|record_res| {
+                futures::future::ready(match record_res {
+                    Err(e) => Some(Err(axum::Error::new(e))),
+                    Ok(record) => decode_json_seq_record(&record),
+                })
+            })
+        })
+    }
+}
+
+// Truncated and malformed records are skipped as recommended in RFC 7464,
+// while valid JSON texts that don't match the type are reported as errors
+fn decode_json_seq_record<T>(record: &[u8]) -> Option<Result<T, axum::Error>>
+where
+    T: DeserializeOwned,
+{
+    let json_text = match record.iter().position(|b| !b.is_ascii_whitespace()) {
+        Some(start) => &record[start..],
+        None => return None,
+    };
+
+    // Top-level numbers, `true`, `false` and `null` may be truncated without any visible signs,
+    // so they are valid only with the line feed after them
+    let maybe_truncated = !record.ends_with(JSON_NL_SEP_BYTES)
+        && matches!(json_text[0], b'-' | b'0'..=b'9' | b't' | b'f' | b'n');
+    if maybe_truncated {
+        return None;
+    }
+
+    match serde_json::from_slice(json_text) {
+        Ok(obj) => Some(Ok(obj)),
+        Err(e) if e.is_data() => Some(Err(axum::Error::new(e))),
+        Err(_) => None,
+    }
+}
+
+/// What to do with the stream after writing an error record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonErrorRecordMode {
+    /// Write the error record in place of the failed item and continue with the next items.
+    Continue,
+    /// Write the error record as the final item and close the output properly.
+    Terminate,
+}
+
+type JsonErrorMapper = Arc<dyn Fn(&axum::Error) -> serde_json::Value + Send + Sync>;
+
+#[derive(Clone)]
+struct JsonErrorRecords {
+    mode: JsonErrorRecordMode,
+    mapper: JsonErrorMapper,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum JsonRecord<T> {
+    Item(T),
+    Error(serde_json::Value),
+}
+
+fn json_records<'b, T>(
+    stream: BoxStream<'b, Result<T, axum::Error>>,
+    error_records: &Option<JsonErrorRecords>,
+) -> BoxStream<'b, Result<JsonRecord<T>, axum::Error>>
+where
+    T: Send + 'b,
+{
+    match error_records {
+        None => Box::pin(stream.map_ok(JsonRecord::Item)),
+        Some(error_records) => {
+            let mapper = error_records.mapper.clone();
+            let records = stream.map(move |obj_res| match obj_res {
+                Ok(obj) => Ok(JsonRecord::Item(obj)),
+                Err(e) => Ok(JsonRecord::Error(mapper(&e))),
+            });
+            match error_records.mode {
+                JsonErrorRecordMode::Continue => Box::pin(records),
+                JsonErrorRecordMode::Terminate => {
+                    Box::pin(records.scan(false, |terminated, record_res| {
+                        futures::future::ready(if *terminated {
+                            None
+                        } else {
+                            *terminated = matches!(record_res, Ok(JsonRecord::Error(_)));
+                            Some(record_res)
+                        })
+                    }))
+                }
+            }
+        }
+    }
+}
+
+fn json_array_frames<'b>(
+    stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+    array_field: Option<&str>,
+    options: &StreamBodyFromOptions,
+) -> BoxStream<'b, Result<JsonArrayFrame, axum::Error>> {
+    let reader =
+        StreamReader::new(stream.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)));
+    let codec = JsonArrayCodec::new(array_field, options.max_obj_len);
+    Box::pin(FramedRead::new(reader, codec).map_err(axum::Error::new))
+}
+
+enum JsonArrayFrame {
+    Item(axum::body::Bytes),
+    Envelope(axum::body::Bytes),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JsonArrayCodecState {
+    Prefix,
+    Items,
+    Suffix,
+    Done,
+}
+
+/// Incremental JSON array decoder.
+/// It doesn't validate JSON itself, only finds the boundaries of the array items (and the envelope around
+/// the array if it is specified), so the items can be deserialized independently.
+struct JsonArrayCodec {
+    array_field: Option<Vec<u8>>,
+    max_obj_len: Option<usize>,
+    state: JsonArrayCodecState,
+    pos: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    string_start: usize,
+    last_key: Option<(usize, usize)>,
+    array_field_found: bool,
+    after_separator: bool,
+    envelope: BytesMut,
+}
+
+impl JsonArrayCodec {
+    fn new(array_field: Option<&str>, max_obj_len: Option<usize>) -> Self {
+        Self {
+            array_field: array_field.map(|field| {
+                serde_json::to_vec(field).unwrap_or_else(|_| field.as_bytes().to_vec())
+            }),
+            max_obj_len,
+            state: JsonArrayCodecState::Prefix,
+            pos: 0,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            string_start: 0,
+            last_key: None,
+            array_field_found: false,
+            after_separator: false,
+            envelope: BytesMut::new(),
+        }
+    }
+
+    fn invalid_data(&mut self, message: &str) -> std::io::Error {
+        self.state = JsonArrayCodecState::Done;
+        std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+    }
+
+    fn check_max_obj_len(&mut self, len: usize) -> Result<(), std::io::Error> {
+        match self.max_obj_len {
+            Some(max_obj_len) if len > max_obj_len => {
+                Err(self.invalid_data(&format!("Object length exceeds {}", max_obj_len)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Returns true when the byte was a part of a JSON string
+    fn scan_string(&mut self, byte: u8) -> bool {
+        if self.in_string {
+            if self.escaped {
+                self.escaped = false;
+            } else if byte == b'\\' {
+                self.escaped = true;
+            } else if byte == b'"' {
+                self.in_string = false;
+            }
+            true
+        } else if byte == b'"' {
+            self.in_string = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn decode_prefix(&mut self, src: &mut BytesMut) -> Result<bool, std::io::Error> {
+        while self.pos < src.len() {
+            let byte = src[self.pos];
+            let at_pos = self.pos;
+            self.pos += 1;
+
+            if self.array_field.is_none() {
+                if byte == b'[' {
+                    src.advance(self.pos);
+                    self.pos = 0;
+                    return Ok(true);
+                } else if !byte.is_ascii_whitespace() {
+                    return Err(self.invalid_data("Expected JSON array"));
+                }
+                continue;
+            }
+
+            let was_in_string = self.in_string;
+            if self.scan_string(byte) {
+                if self.depth == 1 && !was_in_string {
+                    self.string_start = at_pos;
+                } else if self.depth == 1 && !self.in_string {
+                    self.last_key = Some((self.string_start, self.pos));
+                }
+                continue;
+            }
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+            if self.depth == 1 && byte == b':' {
+                self.array_field_found = match self.last_key.take() {
+                    Some((start, end)) => self.array_field.as_deref() == Some(&src[start..end]),
+                    None => false,
+                };
+                continue;
+            }
+            if self.depth == 1 && byte == b'[' && self.array_field_found {
+                self.envelope.extend_from_slice(&src[..at_pos]);
+                self.envelope.extend_from_slice(b"[]");
+                src.advance(self.pos);
+                self.pos = 0;
+                self.depth = 0;
+                return Ok(true);
+            }
+            self.array_field_found = false;
+            self.last_key = None;
+            match byte {
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' if self.depth > 0 => self.depth -= 1,
+                _ => {}
+            }
+            if self.depth == 0 {
+                return Err(self.invalid_data("Array field not found in JSON envelope"));
+            }
+        }
+        self.check_max_obj_len(src.len())?;
+        Ok(false)
+    }
+
+    fn decode_items(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<axum::body::Bytes>, std::io::Error> {
+        while self.pos < src.len() {
+            let byte = src[self.pos];
+            let at_pos = self.pos;
+            self.pos += 1;
+
+            if self.scan_string(byte) {
+                continue;
+            }
+            match byte {
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' if self.depth > 0 => self.depth -= 1,
+                b',' | b']' if self.depth == 0 => {
+                    if byte == b']' {
+                        self.state = JsonArrayCodecState::Suffix;
+                    }
+                    let item = src.split_to(at_pos).freeze();
+                    src.advance(1);
+                    self.pos = 0;
+                    let after_separator = self.after_separator;
+                    self.after_separator = byte == b',';
+                    if !item.iter().all(|b| b.is_ascii_whitespace()) {
+                        return Ok(Some(item));
+                    } else if byte == b',' {
+                        return Err(self.invalid_data("Unexpected ',' in JSON array"));
+                    } else if after_separator {
+                        return Err(self.invalid_data("Trailing ',' in JSON array"));
+                    } else {
+                        return Ok(None);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.check_max_obj_len(src.len())?;
+        Ok(None)
+    }
+
+    fn decode_suffix(&mut self, src: &mut BytesMut) -> Result<(), std::io::Error> {
+        if self.array_field.is_some() {
+            self.envelope.extend_from_slice(src);
+            src.clear();
+            self.check_max_obj_len(self.envelope.len())
+        } else if src.iter().all(|b| b.is_ascii_whitespace()) {
+            src.clear();
+            Ok(())
+        } else {
+            Err(self.invalid_data("Unexpected data after JSON array"))
+        }
+    }
+}
+
+impl tokio_util::codec::Decoder for JsonArrayCodec {
+    type Item = JsonArrayFrame;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            match self.state {
+                JsonArrayCodecState::Prefix => {
+                    if self.decode_prefix(src)? {
+                        self.state = JsonArrayCodecState::Items;
+                    } else {
+                        return Ok(None);
+                    }
+                }
+                JsonArrayCodecState::Items => match self.decode_items(src)? {
+                    Some(item) => return Ok(Some(JsonArrayFrame::Item(item))),
+                    None if self.state == JsonArrayCodecState::Items => return Ok(None),
+                    None => {}
+                },
+                JsonArrayCodecState::Suffix => {
+                    self.decode_suffix(src)?;
+                    return Ok(None);
+                }
+                JsonArrayCodecState::Done => {
+                    src.clear();
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None => match self.state {
+                JsonArrayCodecState::Prefix | JsonArrayCodecState::Items => {
+                    Err(self.invalid_data("Unexpected end of JSON array"))
+                }
+                JsonArrayCodecState::Suffix if self.array_field.is_some() => {
+                    self.state = JsonArrayCodecState::Done;
+                    Ok(Some(JsonArrayFrame::Envelope(
+                        self.envelope.split().freeze(),
+                    )))
+                }
+                _ => Ok(None),
+            },
+        }
+    }
 }
 
 const JSON_ARRAY_BEGIN_BYTES: &[u8] = "[".as_bytes();
@@ -182,6 +876,8 @@ const JSON_ARRAY_ENVELOP_END_BYTES: &[u8] = "]}".as_bytes();
 const JSON_SEP_BYTES: &[u8] = ",".as_bytes();
 
 const JSON_NL_SEP_BYTES: &[u8] = "\n".as_bytes();
+const JSON_CRLF_SEP_BYTES: &[u8] = "\r\n".as_bytes();
+const JSON_SEQ_RS_BYTES: &[u8] = &[0x1E];
 
 impl<'a> crate::StreamBodyAs<'a> {
     pub fn json_array<S, T>(stream: S) -> Self
@@ -252,6 +948,23 @@ impl<'a> crate::StreamBodyAs<'a> {
     {
         Self::new(JsonNewLineStreamFormat::new(), stream)
     }
+
+    pub fn json_seq<S, T>(stream: S) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = T> + 'a + Send,
+    {
+        Self::new(JsonSeqStreamFormat::new(), stream.map(Ok::<T, axum::Error>))
+    }
+
+    pub fn json_seq_with_errors<S, T, E>(stream: S) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = Result<T, E>> + 'a + Send,
+        E: Into<axum::Error>,
+    {
+        Self::new(JsonSeqStreamFormat::new(), stream)
+    }
 }
 
 impl StreamBodyAsOptions {
@@ -333,6 +1046,94 @@ impl StreamBodyAsOptions {
     {
         StreamBodyAs::with_options(JsonNewLineStreamFormat::new(), stream, self)
     }
+
+    pub fn json_seq<'a, S, T>(self, stream: S) -> StreamBodyAs<'a>
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = T> + 'a + Send,
+    {
+        StreamBodyAs::with_options(
+            JsonSeqStreamFormat::new(),
+            stream.map(Ok::<T, axum::Error>),
+            self,
+        )
+    }
+
+    pub fn json_seq_with_errors<'a, S, T, E>(self, stream: S) -> StreamBodyAs<'a>
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = Result<T, E>> + 'a + Send,
+        E: Into<axum::Error>,
+    {
+        StreamBodyAs::with_options(JsonSeqStreamFormat::new(), stream, self)
+    }
+}
+
+impl StreamBodyFrom {
+    pub fn json_array<T>(self) -> BoxStream<'static, Result<T, axum::Error>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.decode(JsonArrayStreamFormat::new())
+    }
+
+    /// Decode the items of the array located in the `array_field` of the envelope object.
+    /// The envelope itself (with the empty array instead of the items) is available
+    /// through the returned receiver after the stream ends.
+    pub fn json_array_with_envelope<T, EN>(
+        self,
+        array_field: &str,
+    ) -> (
+        BoxStream<'static, Result<T, axum::Error>>,
+        oneshot::Receiver<EN>,
+    )
+    where
+        T: DeserializeOwned + Send + 'static,
+        EN: DeserializeOwned + Send + 'static,
+    {
+        let (envelope_sender, envelope_receiver) = oneshot::channel();
+        let mut envelope_sender = Some(envelope_sender);
+        let (body, options) = self.into_parts();
+
+        let stream = json_array_frames(
+            Box::pin(body.into_data_stream()),
+            Some(array_field),
+            &options,
+        )
+        .filter_map(move |frame_res| {
+            futures::future::ready(match frame_res {
+                Err(e) => Some(Err(e)),
+                Ok(JsonArrayFrame::Item(bytes)) => {
+                    Some(serde_json::from_slice(&bytes).map_err(axum::Error::new))
+                }
+                Ok(JsonArrayFrame::Envelope(bytes)) => match serde_json::from_slice::<EN>(&bytes) {
+                    Ok(envelope) => {
+                        if let Some(sender) = envelope_sender.take() {
+                            let _ = sender.send(envelope);
+                        }
+                        None
+                    }
+                    Err(e) => Some(Err(axum::Error::new(e))),
+                },
+            })
+        });
+
+        (Box::pin(stream), envelope_receiver)
+    }
+
+    pub fn json_nl<T>(self) -> BoxStream<'static, Result<T, axum::Error>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.decode(JsonNewLineStreamFormat::new())
+    }
+
+    pub fn json_seq<T>(self) -> BoxStream<'static, Result<T, axum::Error>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.decode(JsonSeqStreamFormat::new())
+    }
 }
 
 #[cfg(test)]
@@ -340,8 +1141,10 @@ mod tests {
     use super::*;
     use crate::test_client::*;
     use crate::StreamBodyAs;
+    use axum::response::IntoResponse;
     use axum::{routing::*, Router};
     use futures::stream;
+    use serde::Deserialize;
 
     #[tokio::test]
     async fn serialize_json_array_stream_format() {
@@ -433,6 +1236,40 @@ mod tests {
         assert_eq!(body, expected_json);
     }
 
+    #[tokio::test]
+    async fn serialize_json_nl_stream_format_content_type_and_terminator() {
+        let app = Router::new()
+            .route(
+                "/ndjson",
+                get(|| async {
+                    StreamBodyAs::new(
+                        JsonNewLineStreamFormat::new()
+                            .with_content_type(JsonNewLineContentType::NdJson)
+                            .with_terminator(JsonNewLineTerminator::CrLf),
+                        stream::iter(vec![Ok::<_, axum::Error>(1), Ok(2)]),
+                    )
+                }),
+            )
+            .route(
+                "/custom",
+                get(|| async {
+                    StreamBodyAsOptions::new()
+                        .content_type(crate::HttpHeaderValue::from_static("application/x-custom"))
+                        .json_nl(stream::iter(vec![1, 2]))
+                }),
+            );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/ndjson").send().await.unwrap();
+        assert_eq!(res.headers()["content-type"], "application/x-ndjson");
+        assert_eq!(res.text().await.unwrap(), "1\r\n2\r\n");
+
+        let res = client.get("/custom").send().await.unwrap();
+        assert_eq!(res.headers()["content-type"], "application/x-custom");
+        assert_eq!(res.text().await.unwrap(), "1\n2\n");
+    }
+
     #[tokio::test]
     async fn serialize_json_array_stream_with_envelope_format() {
         #[derive(Debug, Clone, Serialize)]
@@ -547,4 +1384,422 @@ mod tests {
 
         assert_eq!(body, expected_json);
     }
+
+    #[tokio::test]
+    async fn serialize_json_array_stream_with_error_records() {
+        #[derive(Debug, Clone, Serialize)]
+        struct TestOutputStructure {
+            foo: String,
+        }
+
+        // axum::Error isn't Clone, so the stream is created in the handler
+        let test_stream = || {
+            stream::iter(vec![
+                Ok(TestOutputStructure {
+                    foo: "bar".to_string(),
+                }),
+                Err(axum::Error::new("test error")),
+                Ok(TestOutputStructure {
+                    foo: "baz".to_string(),
+                }),
+            ])
+        };
+
+        let app = Router::new().route(
+            "/",
+            get(move || async move {
+                StreamBodyAs::new(
+                    JsonArrayStreamFormat::with_envelope(
+                        serde_json::json!({ "status": "ok" }),
+                        "data",
+                    )
+                    .with_error_records(
+                        JsonErrorRecordMode::Terminate,
+                        |e| serde_json::json!({ "error": e.to_string() }),
+                    ),
+                    test_stream(),
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap();
+        let body = res.text().await.unwrap();
+
+        assert_eq!(
+            body,
+            r#"{"status":"ok","data":[{"foo":"bar"},{"error":"test error"}]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn serialize_json_nl_stream_with_error_records() {
+        let test_stream = || stream::iter(vec![Ok(1), Err(axum::Error::new("test error")), Ok(2)]);
+
+        let app = Router::new().route(
+            "/",
+            get(move || async move {
+                StreamBodyAs::new(
+                    JsonNewLineStreamFormat::new().with_error_records(
+                        JsonErrorRecordMode::Continue,
+                        |e| serde_json::json!({ "error": e.to_string() }),
+                    ),
+                    test_stream(),
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap();
+        let body = res.text().await.unwrap();
+
+        assert_eq!(body, "1\n{\"error\":\"test error\"}\n2\n");
+    }
+
+    #[tokio::test]
+    async fn serialize_json_array_stream_with_terminate_error_policy() {
+        let test_stream = || stream::iter(vec![Ok(1), Ok(2), Err(axum::Error::new("test error"))]);
+
+        let app = Router::new().route(
+            "/",
+            get(move || async move {
+                StreamBodyAsOptions::new()
+                    .error_policy(crate::StreamErrorPolicy::Terminate)
+                    .json_array_with_errors(test_stream())
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap();
+        let body = res.text().await.unwrap();
+
+        assert_eq!(body, "[1,2]");
+    }
+
+    #[tokio::test]
+    async fn serialize_json_array_stream_with_serialization_errors() {
+        // Fails to serialize the negative numbers
+        struct TestOutputStructure(i32);
+
+        impl Serialize for TestOutputStructure {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if self.0 < 0 {
+                    Err(serde::ser::Error::custom("negative number"))
+                } else {
+                    serializer.serialize_i32(self.0)
+                }
+            }
+        }
+
+        let test_stream =
+            || stream::iter(vec![-1, 1, -2, 2, 3].into_iter().map(TestOutputStructure));
+
+        let app = Router::new()
+            .route(
+                "/skip",
+                get(move || async move {
+                    StreamBodyAsOptions::new()
+                        .error_policy(crate::StreamErrorPolicy::skip(|_| {}))
+                        .json_array(test_stream())
+                }),
+            )
+            .route(
+                "/terminate",
+                get(move || async move {
+                    StreamBodyAsOptions::new()
+                        .error_policy(crate::StreamErrorPolicy::Terminate)
+                        .json_array(test_stream().skip(1))
+                }),
+            )
+            .route(
+                "/nl",
+                get(move || async move {
+                    StreamBodyAsOptions::new()
+                        .error_policy(crate::StreamErrorPolicy::skip(|_| {}))
+                        .json_nl(test_stream())
+                }),
+            );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/skip").send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "[1,2,3]");
+
+        let res = client.get("/terminate").send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "[1]");
+
+        let res = client.get("/nl").send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "1\n2\n3\n");
+
+        // The envelope errors abort the response regardless of the policy
+        let envelope_res: Result<Vec<axum::body::Bytes>, axum::Error> = StreamBodyAsOptions::new()
+            .error_policy(crate::StreamErrorPolicy::Terminate)
+            .json_array_with_envelope(test_stream().skip(1), TestOutputStructure(-1), "items")
+            .into_response()
+            .into_body()
+            .into_data_stream()
+            .try_collect()
+            .await;
+        assert!(envelope_res.is_err());
+    }
+
+    #[tokio::test]
+    async fn serialize_json_seq_stream_format() {
+        #[derive(Debug, Clone, Serialize)]
+        struct TestOutputStructure {
+            foo: String,
+        }
+
+        let test_stream_vec = vec![
+            TestOutputStructure {
+                foo: "bar\nbaz".to_string()
+            };
+            7
+        ];
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_seq(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let expected_json_seq = test_stream_vec
+            .iter()
+            .map(|item| format!("\x1E{}\n", serde_json::to_string(item).unwrap()))
+            .collect::<String>();
+
+        let res = client.get("/").send().await.unwrap();
+        assert_eq!(
+            res.headers()
+                .get("content-type")
+                .and_then(|h| h.to_str().ok()),
+            Some("application/json-seq")
+        );
+
+        let body = res.text().await.unwrap();
+
+        assert_eq!(body, expected_json_seq);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_seq_stream_format_truncated_records() {
+        #[derive(Debug, Clone, PartialEq, Deserialize)]
+        struct TestInputStructure {
+            foo: String,
+        }
+
+        let request_body = "\x1E{\"foo\":\"bar1\"}\n\x1E{\"foo\":\"ba\x1E\x1E{\"foo\":\"bar2\"}\n\x1E123\x1E{\"foo\":\"bar3\"}\n";
+        let body = StreamBodyFrom::new(axum::body::Body::from(request_body));
+
+        let items: Vec<TestInputStructure> = body.json_seq().try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                TestInputStructure {
+                    foo: "bar1".to_string()
+                },
+                TestInputStructure {
+                    foo: "bar2".to_string()
+                },
+                TestInputStructure {
+                    foo: "bar3".to_string()
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_stream_format() {
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        struct TestInputStructure {
+            foo: String,
+        }
+
+        let test_stream_vec = vec![
+            TestInputStructure {
+                foo: "bar".to_string()
+            };
+            7
+        ];
+
+        let app = Router::new().route(
+            "/",
+            post(|body: StreamBodyFrom| async {
+                let items: Vec<TestInputStructure> = body.json_nl().try_collect().await.unwrap();
+                axum::Json(items)
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let request_body = test_stream_vec
+            .iter()
+            .map(|item| serde_json::to_string(item).unwrap())
+            .collect::<Vec<String>>()
+            .join("\n")
+            + "\n";
+
+        // Small chunks to make sure objects are split across HTTP frames
+        let request_chunks: Vec<Result<Vec<u8>, std::io::Error>> = request_body
+            .as_bytes()
+            .chunks(5)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect();
+
+        let res = client
+            .post("/")
+            .body(reqwest::Body::wrap_stream(stream::iter(request_chunks)))
+            .send()
+            .await
+            .unwrap();
+
+        let items: Vec<TestInputStructure> = res.json().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_stream_format_max_obj_len() {
+        #[derive(Debug, Clone, PartialEq, Deserialize)]
+        struct TestInputStructure {
+            foo: String,
+        }
+
+        let body = StreamBodyFrom::new(axum::body::Body::from(
+            "{\"foo\":\"bar\"}\n{\"foo\":\"too long bar\"}\n",
+        ))
+        .with_options(StreamBodyFromOptions::new().max_obj_len(16));
+
+        let items: Vec<Result<TestInputStructure, axum::Error>> = body.json_nl().collect().await;
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].as_ref().unwrap(),
+            &TestInputStructure {
+                foo: "bar".to_string()
+            }
+        );
+        assert!(items[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_format() {
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        struct TestInputStructure {
+            foo: String,
+            bar: Vec<i32>,
+        }
+
+        let test_stream_vec = vec![
+            TestInputStructure {
+                foo: "[\"bar\", {}]\\".to_string(),
+                bar: vec![1, 2, 3]
+            };
+            7
+        ];
+
+        let app = Router::new().route(
+            "/",
+            post(|body: StreamBodyFrom| async {
+                let items: Vec<TestInputStructure> = body.json_array().try_collect().await.unwrap();
+                axum::Json(items)
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let request_body = serde_json::to_string_pretty(&test_stream_vec).unwrap();
+
+        let request_chunks: Vec<Result<Vec<u8>, std::io::Error>> = request_body
+            .as_bytes()
+            .chunks(3)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect();
+
+        let res = client
+            .post("/")
+            .body(reqwest::Body::wrap_stream(stream::iter(request_chunks)))
+            .send()
+            .await
+            .unwrap();
+
+        let items: Vec<TestInputStructure> = res.json().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_with_envelope_format() {
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        struct TestItemStructure {
+            foo: String,
+            bar: i32,
+        }
+
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        struct TestEnvelopeStructure {
+            envelope_field: String,
+            my_array: Vec<TestItemStructure>,
+            other_field: Vec<String>,
+        }
+
+        let test_envelope = TestEnvelopeStructure {
+            envelope_field: "my_array".to_string(),
+            my_array: vec![
+                TestItemStructure {
+                    foo: "bar".to_string(),
+                    bar: 42
+                };
+                7
+            ],
+            other_field: vec!["my_array".to_string()],
+        };
+
+        let request_body = serde_json::to_vec(&test_envelope).unwrap();
+        let request_chunks: Vec<Result<axum::body::Bytes, std::io::Error>> = request_body
+            .chunks(4)
+            .map(|chunk| Ok(axum::body::Bytes::copy_from_slice(chunk)))
+            .collect();
+
+        let body = StreamBodyFrom::new(axum::body::Body::from_stream(stream::iter(request_chunks)));
+
+        let (items_stream, envelope_receiver) =
+            body.json_array_with_envelope::<TestItemStructure, TestEnvelopeStructure>("my_array");
+
+        let items: Vec<TestItemStructure> = items_stream.try_collect().await.unwrap();
+        assert_eq!(items, test_envelope.my_array);
+
+        let envelope = envelope_receiver.await.unwrap();
+        assert_eq!(
+            envelope,
+            TestEnvelopeStructure {
+                my_array: Vec::new(),
+                ..test_envelope
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_trailing_comma() {
+        let items: Vec<Result<i32, axum::Error>> =
+            StreamBodyFrom::new(axum::body::Body::from("[1, 2, ]"))
+                .json_array::<i32>()
+                .collect()
+                .await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_ref().unwrap(), &1);
+        assert_eq!(items[1].as_ref().unwrap(), &2);
+        assert!(items[2].is_err());
+
+        let items: Vec<i32> = StreamBodyFrom::new(axum::body::Body::from(" [ ] "))
+            .json_array::<i32>()
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(items.is_empty());
+    }
 }
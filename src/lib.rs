@@ -4,10 +4,14 @@
 //! # axum HTTP streaming body support for different formats:
 //! - JSON array stream format
 //! - JSON Lines (NL/NewLines) format
+//! - JSON Text Sequences (RFC 7464) format
 //! - CSV stream format
 //! - Protobuf len-prefixed stream format
 //! - Arrow IPC stream format
 //! - Text stream format
+//! - Server-Sent Events (SSE) stream format
+//! - MessagePack stream format
+//! - CBOR sequence stream format
 //!
 //! [JSON Streaming](https://en.wikipedia.org/wiki/JSON_streaming) is a term referring to streaming a
 //! stream of element as independent JSON objects as a continuous HTTP request or response.
@@ -62,6 +66,10 @@
 //! }
 //!
 //! ```
+//!
+//! ## Streaming request bodies
+//! Use [`StreamBodyFrom`] extractor to decode request bodies incrementally as a stream of your objects.
+//!
 //! ## Need client support?
 //! There is the same functionality for:
 //! - [reqwest-streams](https://github.com/abdolence/reqwest-streams-rs).
@@ -74,6 +82,47 @@ mod stream_body_as;
 pub use self::stream_body_as::HttpHeaderValue;
 pub use self::stream_body_as::StreamBodyAs;
 pub use self::stream_body_as::StreamBodyAsOptions;
+pub use self::stream_body_as::StreamErrorPolicy;
+pub use self::stream_body_as::{
+    STREAM_BYTES_TRAILER, STREAM_ERROR_CODE_TRAILER, STREAM_ERROR_MESSAGE_TRAILER,
+    STREAM_ITEMS_TRAILER, STREAM_LIMITS_HEADER, STREAM_STATUS_TRAILER, STREAM_TRUNCATED_TRAILER,
+};
+
+mod stream_pacing;
+pub use self::stream_pacing::StreamRateLimiter;
+
+mod stream_limits;
+pub use self::stream_limits::{StreamLimitReason, StreamLimits};
+
+mod stream_lifecycle;
+pub use self::stream_lifecycle::{StreamEndReason, StreamSummary};
+
+mod stream_buffering;
+pub use self::stream_buffering::StreamBufferingPolicy;
+
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+mod stream_compression;
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+pub use self::stream_compression::StreamCompression;
+
+#[cfg(feature = "metrics")]
+mod stream_metrics;
+#[cfg(feature = "metrics")]
+pub use self::stream_metrics::{
+    STREAM_BYTES_METRIC, STREAM_DURATION_METRIC, STREAM_FIRST_BYTE_METRIC,
+    STREAM_FRAME_SIZE_METRIC, STREAM_ITEMS_METRIC,
+};
+
+#[cfg(feature = "tracing")]
+mod stream_tracing;
+
+mod stream_negotiation;
+pub use self::stream_negotiation::{StreamBodyAsNegotiator, StreamFormatNegotiation};
+
+mod stream_body_from;
+pub use self::stream_body_from::StreamBodyFrom;
+pub use self::stream_body_from::StreamBodyFromOptions;
+pub use self::stream_body_from::DEFAULT_MAX_OBJ_LEN;
 
 mod envelope;
 pub use envelope::*;
@@ -83,7 +132,13 @@ mod json_formats;
 #[cfg(feature = "json")]
 pub use json_formats::JsonArrayStreamFormat;
 #[cfg(feature = "json")]
-pub use json_formats::JsonNewLineStreamFormat;
+pub use json_formats::JsonErrorRecordMode;
+#[cfg(feature = "json")]
+pub use json_formats::JsonSeqStreamFormat;
+#[cfg(feature = "json")]
+pub use json_formats::{JsonNewLineContentType, JsonNewLineTerminator};
+#[cfg(feature = "json")]
+pub use json_formats::{JsonNewLineStreamFormat, JsonNewLineStreamFormatBuilder};
 
 #[cfg(feature = "csv")]
 mod csv_format;
@@ -102,6 +157,21 @@ mod protobuf_format;
 #[cfg(feature = "protobuf")]
 pub use protobuf_format::ProtobufStreamFormat;
 
+#[cfg(feature = "msgpack")]
+mod msgpack_format;
+#[cfg(feature = "msgpack")]
+pub use msgpack_format::MessagePackStreamFormat;
+
+#[cfg(feature = "cbor")]
+mod cbor_format;
+#[cfg(feature = "cbor")]
+pub use cbor_format::CborSeqStreamFormat;
+
+#[cfg(feature = "sse")]
+mod sse_format;
+#[cfg(feature = "sse")]
+pub use sse_format::{SseEvent, SseStreamFormat};
+
 #[cfg(feature = "arrow")]
 mod arrow_format;
 #[cfg(feature = "arrow")]
@@ -0,0 +1,606 @@
+use crate::stream_body_as::StreamBodyAsOptions;
+use crate::stream_body_from::StreamBodyFromOptions;
+use crate::stream_format::{StreamingDecodeFormat, StreamingFormat};
+use crate::{StreamBodyAs, StreamBodyFrom};
+use bytes::{Buf, BytesMut};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use futures::{Stream, TryStreamExt};
+use http::HeaderMap;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+use tokio_util::codec::FramedRead;
+use tokio_util::io::StreamReader;
+
+/// MessagePack format writing the items as a sequence of concatenated MessagePack values.
+pub struct MessagePackStreamFormat {
+    array: bool,
+}
+
+impl MessagePackStreamFormat {
+    pub fn new() -> Self {
+        Self { array: false }
+    }
+
+    /// Write the items as a MessagePack array with the length from the stream size hint,
+    /// when the size hint is exact (e.g. from an iterator without the error policy or the limits ending it early),
+    /// otherwise the items are written as the concatenated values.
+    /// The decoder with this option expects the array.
+    pub fn with_array(mut self) -> Self {
+        self.array = true;
+        self
+    }
+}
+
+impl<T> StreamingFormat<T> for MessagePackStreamFormat
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    fn to_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<T, axum::Error>>,
+        options: &'a StreamBodyAsOptions,
+    ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
+        // The array length is written before the items, so it is known only with the exact size hint
+        let array_len = match stream.size_hint() {
+            (lower, Some(upper)) if self.array && lower == upper => u32::try_from(lower).ok(),
+            _ => None,
+        };
+        let array_len = match array_len {
+            Some(array_len) => array_len,
+            None => {
+                return Box::pin(options.error_policy_adapter(stream.map(|obj_res| {
+                    match obj_res {
+                        Err(e) => Err(e),
+                        Ok(obj) => rmp_serde::to_vec_named(&obj)
+                            .map(axum::body::Bytes::from)
+                            .map_err(axum::Error::new),
+                    }
+                })))
+            }
+        };
+
+        let mut array_header = Vec::new();
+        let prepend_stream: BoxStream<Result<axum::body::Bytes, axum::Error>> =
+            Box::pin(futures::stream::once(futures::future::ready(
+                rmp::encode::write_array_len(&mut array_header, array_len)
+                    .map(|_| axum::body::Bytes::from(array_header))
+                    .map_err(axum::Error::new),
+            )));
+
+        // The stream must produce exactly the number of items written in the array header,
+        // so the serialization errors abort the response regardless of the error policy
+        let stream_bytes: BoxStream<Result<axum::body::Bytes, axum::Error>> = Box::pin(
+            stream
+                .map(Some)
+                .chain(futures::stream::once(futures::future::ready(None)))
+                .scan(0u32, move |items_count, obj_res| {
+                    futures::future::ready(Some(match obj_res {
+                        Some(Err(e)) => Some(Err(e)),
+                        Some(Ok(_)) if *items_count >= array_len => Some(Err(axum::Error::new(
+                            invalid_data("The stream has more items than its size hint"),
+                        ))),
+                        Some(Ok(obj)) => {
+                            *items_count += 1;
+                            Some(
+                                rmp_serde::to_vec_named(&obj)
+                                    .map(axum::body::Bytes::from)
+                                    .map_err(axum::Error::new),
+                            )
+                        }
+                        None if *items_count < array_len => Some(Err(axum::Error::new(
+                            invalid_data("The stream has fewer items than its size hint"),
+                        ))),
+                        None => None,
+                    }))
+                })
+                .filter_map(futures::future::ready),
+        );
+
+        Box::pin(prepend_stream.chain(stream_bytes))
+    }
+
+    fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
+        let mut header_map = HeaderMap::new();
+        header_map.insert(
+            http::header::CONTENT_TYPE,
+            options
+                .content_type
+                .clone()
+                .unwrap_or_else(|| http::header::HeaderValue::from_static("application/msgpack")),
+        );
+        Some(header_map)
+    }
+
+    fn format_name(&self) -> &'static str {
+        "msgpack"
+    }
+}
+
+impl<T> StreamingDecodeFormat<T> for MessagePackStreamFormat
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    fn decode_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        options: &'a StreamBodyFromOptions,
+    ) -> BoxStream<'b, Result<T, axum::Error>> {
+        let reader = StreamReader::new(
+            stream.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+        );
+        let codec = MessagePackCodec::<T>::new(self.array, options.max_obj_len);
+
+        Box::pin(
+            FramedRead::new(reader, codec)
+                .map(|obj_res| obj_res.and_then(|obj| obj).map_err(axum::Error::new)),
+        )
+    }
+}
+
+/// Incremental MessagePack values decoder.
+/// The values are deserialized as soon as they are complete in the buffer,
+/// and the values failed to deserialize are reported individually without ending the stream.
+struct MessagePackCodec<T> {
+    max_obj_len: Option<usize>,
+    array: bool,
+    remaining_items: Option<u32>,
+    scanner: MessagePackScanner,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<T> MessagePackCodec<T> {
+    fn new(array: bool, max_obj_len: Option<usize>) -> Self {
+        Self {
+            max_obj_len,
+            array,
+            remaining_items: None,
+            scanner: MessagePackScanner::default(),
+            _item: PhantomData,
+        }
+    }
+}
+
+/// Finds the end of the MessagePack value in the buffer by its markers and lengths,
+/// resuming from the position where the previous incomplete scan stopped,
+/// so the buffer is scanned once regardless of how the value arrives.
+#[derive(Default)]
+struct MessagePackScanner {
+    offset: usize,
+    // The number of values remaining in the nested arrays and maps
+    pending: Vec<u64>,
+}
+
+impl MessagePackScanner {
+    // Returns the length of the value when it is complete
+    fn scan(
+        &mut self,
+        src: &[u8],
+        max_obj_len: Option<usize>,
+    ) -> Result<Option<usize>, std::io::Error> {
+        loop {
+            let (header_len, payload_len, values) = match Self::read_header(&src[self.offset..])? {
+                Some(header) => header,
+                None => return Ok(None),
+            };
+            let value_end = usize::try_from(payload_len)
+                .ok()
+                .and_then(|payload_len| payload_len.checked_add(header_len))
+                .and_then(|value_len| value_len.checked_add(self.offset))
+                .ok_or_else(|| invalid_data("MessagePack value length is too large"))?;
+            if let Some(max_obj_len) = max_obj_len.filter(|max| value_end > *max) {
+                return Err(invalid_data(format!(
+                    "Object length exceeds {}",
+                    max_obj_len
+                )));
+            }
+            if src.len() < value_end {
+                return Ok(None);
+            }
+            self.offset = value_end;
+
+            if values > 0 {
+                self.pending.push(values);
+                continue;
+            }
+            // The value is complete, so are the containers with no more values
+            loop {
+                match self.pending.last_mut() {
+                    None => {
+                        let value_len = self.offset;
+                        self.offset = 0;
+                        return Ok(Some(value_len));
+                    }
+                    Some(remaining) if *remaining > 1 => {
+                        *remaining -= 1;
+                        break;
+                    }
+                    Some(_) => {
+                        self.pending.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns the header length, the payload length and the number of nested values
+    fn read_header(src: &[u8]) -> Result<Option<(usize, u64, u64)>, std::io::Error> {
+        let marker = match src.first() {
+            Some(marker) => *marker,
+            None => return Ok(None),
+        };
+        let read_len = |size: usize| -> Option<u64> {
+            src.get(1..1 + size)
+                .map(|bytes| bytes.iter().fold(0u64, |len, byte| len << 8 | *byte as u64))
+        };
+        let header = match marker {
+            0x00..=0x7f | 0xc0 | 0xc2 | 0xc3 | 0xe0..=0xff => Some((1, 0, 0)),
+            0x80..=0x8f => Some((1, 0, 2 * (marker & 0x0f) as u64)),
+            0x90..=0x9f => Some((1, 0, (marker & 0x0f) as u64)),
+            0xa0..=0xbf => Some((1, (marker & 0x1f) as u64, 0)),
+            0xc1 => return Err(invalid_data("Invalid MessagePack marker")),
+            // bin 8/16/32, str 8/16/32
+            0xc4 | 0xd9 => read_len(1).map(|len| (2, len, 0)),
+            0xc5 | 0xda => read_len(2).map(|len| (3, len, 0)),
+            0xc6 | 0xdb => read_len(4).map(|len| (5, len, 0)),
+            // ext 8/16/32 with the type byte after the length
+            0xc7 => read_len(1).map(|len| (3, len, 0)),
+            0xc8 => read_len(2).map(|len| (4, len, 0)),
+            0xc9 => read_len(4).map(|len| (6, len, 0)),
+            // float 32/64, uint 8/16/32/64, int 8/16/32/64
+            0xca => Some((1, 4, 0)),
+            0xcb => Some((1, 8, 0)),
+            0xcc | 0xd0 => Some((1, 1, 0)),
+            0xcd | 0xd1 => Some((1, 2, 0)),
+            0xce | 0xd2 => Some((1, 4, 0)),
+            0xcf | 0xd3 => Some((1, 8, 0)),
+            // fixext 1/2/4/8/16 with the type byte
+            0xd4 => Some((1, 2, 0)),
+            0xd5 => Some((1, 3, 0)),
+            0xd6 => Some((1, 5, 0)),
+            0xd7 => Some((1, 9, 0)),
+            0xd8 => Some((1, 17, 0)),
+            // array 16/32, map 16/32
+            0xdc => read_len(2).map(|len| (3, 0, len)),
+            0xdd => read_len(4).map(|len| (5, 0, len)),
+            0xde => read_len(2).map(|len| (3, 0, 2 * len)),
+            0xdf => read_len(4).map(|len| (5, 0, 2 * len)),
+        };
+        Ok(header)
+    }
+}
+
+fn invalid_data<E>(e: E) -> std::io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
+fn is_unexpected_eof(e: &std::io::Error) -> bool {
+    e.kind() == std::io::ErrorKind::UnexpectedEof
+}
+
+impl<T> tokio_util::codec::Decoder for MessagePackCodec<T>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<T, std::io::Error>;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+
+        if self.array && self.remaining_items.is_none() {
+            let mut header = &src[..];
+            match rmp::decode::read_array_len(&mut header) {
+                Ok(array_len) => {
+                    src.advance(src.len() - header.len());
+                    self.remaining_items = Some(array_len);
+                }
+                Err(rmp::decode::ValueReadError::InvalidMarkerRead(e))
+                | Err(rmp::decode::ValueReadError::InvalidDataRead(e))
+                    if is_unexpected_eof(&e) =>
+                {
+                    return Ok(None)
+                }
+                Err(e) => return Err(invalid_data(e.to_string())),
+            }
+        }
+
+        if self.remaining_items == Some(0) {
+            return if src.is_empty() {
+                Ok(None)
+            } else {
+                Err(invalid_data("Unexpected data after MessagePack array"))
+            };
+        }
+
+        match self.scanner.scan(src, self.max_obj_len)? {
+            Some(obj_len) => {
+                let obj_bytes = src.split_to(obj_len);
+                self.remaining_items = self.remaining_items.map(|remaining| remaining - 1);
+                Ok(Some(
+                    rmp_serde::from_slice(&obj_bytes).map_err(invalid_data),
+                ))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(obj) => Ok(Some(obj)),
+            None if !src.is_empty() => Err(invalid_data("Incomplete MessagePack value")),
+            None if self.array && self.remaining_items != Some(0) => {
+                Err(invalid_data("Unexpected end of MessagePack array"))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'a> StreamBodyAs<'a> {
+    pub fn msgpack<S, T>(stream: S) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = T> + 'a + Send,
+    {
+        Self::new(
+            MessagePackStreamFormat::new(),
+            stream.map(Ok::<T, axum::Error>),
+        )
+    }
+
+    pub fn msgpack_with_errors<S, T, E>(stream: S) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = Result<T, E>> + 'a + Send,
+        E: Into<axum::Error>,
+    {
+        Self::new(MessagePackStreamFormat::new(), stream)
+    }
+}
+
+impl StreamBodyAsOptions {
+    pub fn msgpack<'a, S, T>(self, stream: S) -> StreamBodyAs<'a>
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = T> + 'a + Send,
+    {
+        StreamBodyAs::with_options(
+            MessagePackStreamFormat::new(),
+            stream.map(Ok::<T, axum::Error>),
+            self,
+        )
+    }
+
+    pub fn msgpack_with_errors<'a, S, T, E>(self, stream: S) -> StreamBodyAs<'a>
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = Result<T, E>> + 'a + Send,
+        E: Into<axum::Error>,
+    {
+        StreamBodyAs::with_options(MessagePackStreamFormat::new(), stream, self)
+    }
+}
+
+impl StreamBodyFrom {
+    pub fn msgpack<T>(self) -> BoxStream<'static, Result<T, axum::Error>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.decode(MessagePackStreamFormat::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use crate::StreamBodyAs;
+    use axum::{routing::*, Router};
+    use futures::stream;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct TestStructure {
+        foo: String,
+        bar: u32,
+    }
+
+    fn test_stream_vec() -> Vec<TestStructure> {
+        (0..7)
+            .map(|idx| TestStructure {
+                foo: "bar".repeat(idx as usize * 10),
+                bar: idx,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn serialize_msgpack_stream_format() {
+        let test_stream_vec = test_stream_vec();
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                StreamBodyAs::new(
+                    MessagePackStreamFormat::new().with_array(),
+                    test_stream.map(Ok::<_, axum::Error>),
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap();
+        assert_eq!(
+            res.headers()
+                .get("content-type")
+                .and_then(|h| h.to_str().ok()),
+            Some("application/msgpack")
+        );
+        let body = res.bytes().await.unwrap();
+
+        let items: Vec<TestStructure> = rmp_serde::from_slice(&body).unwrap();
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_msgpack_stream_format() {
+        let test_stream_vec = test_stream_vec();
+
+        let app = Router::new().route(
+            "/",
+            post(|body: StreamBodyFrom| async {
+                let items: Vec<TestStructure> = body.msgpack().try_collect().await.unwrap();
+                StreamBodyAs::msgpack(stream::iter(items))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let request_body: Vec<u8> = test_stream_vec
+            .iter()
+            .flat_map(|obj| rmp_serde::to_vec_named(obj).unwrap())
+            .collect();
+
+        let request_chunks: Vec<Result<Vec<u8>, std::io::Error>> = request_body
+            .chunks(7)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect();
+
+        let res = client
+            .post("/")
+            .body(reqwest::Body::wrap_stream(stream::iter(request_chunks)))
+            .send()
+            .await
+            .unwrap();
+        let body = res.bytes().await.unwrap().to_vec();
+
+        assert_eq!(body, request_body);
+    }
+
+    #[tokio::test]
+    async fn deserialize_msgpack_array_stream_format() {
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        struct TestNestedStructure {
+            items: Vec<TestStructure>,
+            values: std::collections::BTreeMap<String, (i64, f64, Option<bool>)>,
+            text: String,
+        }
+
+        let test_stream_vec: Vec<TestNestedStructure> = (0..5)
+            .map(|idx| TestNestedStructure {
+                items: test_stream_vec().into_iter().take(idx).collect(),
+                values: (0..idx)
+                    .map(|key| (key.to_string(), (-(key as i64) * 100_000, 0.5, None)))
+                    .collect(),
+                text: "text".repeat(idx * 100),
+            })
+            .collect();
+        let request_body = rmp_serde::to_vec_named(&test_stream_vec).unwrap();
+        let request_chunks: Vec<Result<axum::body::Bytes, std::io::Error>> = request_body
+            .chunks(3)
+            .map(|chunk| Ok(axum::body::Bytes::copy_from_slice(chunk)))
+            .collect();
+
+        let items: Vec<TestNestedStructure> =
+            StreamBodyFrom::new(axum::body::Body::from_stream(stream::iter(request_chunks)))
+                .decode(MessagePackStreamFormat::new().with_array())
+                .try_collect()
+                .await
+                .unwrap();
+        assert_eq!(items, test_stream_vec);
+
+        // The declared length is checked before buffering the value
+        let mut request_body = Vec::new();
+        rmp::encode::write_array_len(&mut request_body, 1).unwrap();
+        rmp::encode::write_str_len(&mut request_body, u32::MAX).unwrap();
+        let items: Vec<Result<String, axum::Error>> =
+            StreamBodyFrom::new(axum::body::Body::from(request_body))
+                .with_options(StreamBodyFromOptions::new().max_obj_len(1024))
+                .decode(MessagePackStreamFormat::new().with_array())
+                .collect()
+                .await;
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
+    #[tokio::test]
+    async fn serialize_msgpack_array_stream_format_inexact_size() {
+        let expected_items: Vec<TestStructure> = test_stream_vec()
+            .into_iter()
+            .filter(|item| item.bar % 2 == 0)
+            .collect();
+        let test_stream = stream::iter(test_stream_vec()).filter(|item| {
+            let keep = item.bar % 2 == 0;
+            async move { keep }
+        });
+        let body = StreamBodyAs::new(
+            MessagePackStreamFormat::new().with_array(),
+            test_stream.map(Ok::<_, axum::Error>),
+        );
+        let data: Vec<axum::body::Bytes> = axum::response::IntoResponse::into_response(body)
+            .into_body()
+            .into_data_stream()
+            .try_collect()
+            .await
+            .unwrap();
+
+        // Written as the concatenated values without the array
+        let items: Vec<TestStructure> = StreamBodyFrom::new(axum::body::Body::from(data.concat()))
+            .msgpack()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items, expected_items);
+
+        // The same with the error policy, which makes the size hint inexact
+        let body = StreamBodyAs::with_options(
+            MessagePackStreamFormat::new().with_array(),
+            stream::iter(test_stream_vec()).map(Ok::<_, axum::Error>),
+            StreamBodyAsOptions::new().error_policy(crate::StreamErrorPolicy::Terminate),
+        );
+        let data: Vec<axum::body::Bytes> = axum::response::IntoResponse::into_response(body)
+            .into_body()
+            .into_data_stream()
+            .try_collect()
+            .await
+            .unwrap();
+        let items: Vec<TestStructure> = StreamBodyFrom::new(axum::body::Body::from(data.concat()))
+            .msgpack()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items, test_stream_vec());
+    }
+
+    #[tokio::test]
+    async fn deserialize_msgpack_stream_format_item_errors() {
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        struct TestOtherStructure {
+            other: bool,
+        }
+
+        let mut request_body = rmp_serde::to_vec_named(&test_stream_vec()[0]).unwrap();
+        request_body.extend(rmp_serde::to_vec_named(&TestOtherStructure { other: true }).unwrap());
+        request_body.extend(rmp_serde::to_vec_named(&test_stream_vec()[1]).unwrap());
+
+        // The type mismatch is reported for the item, and the next items are decoded
+        let items: Vec<Result<TestStructure, axum::Error>> =
+            StreamBodyFrom::new(axum::body::Body::from(request_body))
+                .msgpack()
+                .collect()
+                .await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_ref().unwrap(), &test_stream_vec()[0]);
+        assert!(items[1].is_err());
+        assert_eq!(items[2].as_ref().unwrap(), &test_stream_vec()[1]);
+    }
+}
@@ -1,10 +1,14 @@
 use crate::stream_body_as::StreamBodyAsOptions;
-use crate::stream_format::StreamingFormat;
-use crate::StreamBodyAs;
+use crate::stream_body_from::StreamBodyFromOptions;
+use crate::stream_format::{StreamingDecodeFormat, StreamingFormat};
+use crate::{StreamBodyAs, StreamBodyFrom};
+use bytes::{Buf, BytesMut};
 use futures::stream::BoxStream;
-use futures::Stream;
 use futures::StreamExt;
+use futures::{Stream, TryStreamExt};
 use http::HeaderMap;
+use tokio_util::codec::FramedRead;
+use tokio_util::io::StreamReader;
 
 pub struct ProtobufStreamFormat;
 
@@ -57,6 +61,97 @@ where
         );
         Some(header_map)
     }
+
+    fn format_name(&self) -> &'static str {
+        "protobuf"
+    }
+}
+
+impl<T> StreamingDecodeFormat<T> for ProtobufStreamFormat
+where
+    T: prost::Message + Default + Send + 'static,
+{
+    fn decode_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        options: &'a StreamBodyFromOptions,
+    ) -> BoxStream<'b, Result<T, axum::Error>> {
+        let reader = StreamReader::new(
+            stream.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+        );
+        let codec = ProtobufLenPrefixCodec {
+            max_obj_len: options.max_obj_len,
+        };
+
+        Box::pin({
+            FramedRead::new(reader, codec).map(|frame_res| match frame_res {
+                Err(e) => Err(axum::Error::new(e)),
+                Ok(frame) => T::decode(frame).map_err(axum::Error::new),
+            })
+        })
+    }
+}
+
+/// Decoder for the varint length-prefixed frames.
+/// It checks the declared length of the message before buffering it,
+/// so the messages with huge length prefixes are rejected immediately.
+struct ProtobufLenPrefixCodec {
+    max_obj_len: Option<usize>,
+}
+
+const MAX_VARINT_LEN: usize = 10;
+
+impl tokio_util::codec::Decoder for ProtobufLenPrefixCodec {
+    type Item = axum::body::Bytes;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let varint_len = match src
+            .iter()
+            .take(MAX_VARINT_LEN)
+            .position(|byte| byte & 0x80 == 0)
+        {
+            Some(last_byte_idx) => last_byte_idx + 1,
+            None if src.len() >= MAX_VARINT_LEN => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Invalid varint length prefix",
+                ));
+            }
+            None => return Ok(None),
+        };
+
+        let obj_len = prost::encoding::decode_varint(&mut &src[..varint_len])
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        if let Some(max_obj_len) = self.max_obj_len {
+            if obj_len > max_obj_len as u64 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "Message length {} exceeds the limit {}",
+                        obj_len, max_obj_len
+                    ),
+                ));
+            }
+        }
+
+        let frame_len = usize::try_from(obj_len)
+            .ok()
+            .and_then(|obj_len| obj_len.checked_add(varint_len))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Message length {} is too large", obj_len),
+                )
+            })?;
+        if src.len() < frame_len {
+            return Ok(None);
+        }
+
+        src.advance(varint_len);
+        Ok(Some(src.split_to(frame_len - varint_len).freeze()))
+    }
 }
 
 impl<'a> StreamBodyAs<'a> {
@@ -104,6 +199,15 @@ impl StreamBodyAsOptions {
     }
 }
 
+impl StreamBodyFrom {
+    pub fn protobuf<T>(self) -> BoxStream<'static, Result<T, axum::Error>>
+    where
+        T: prost::Message + Default + Send + 'static,
+    {
+        self.decode(ProtobufStreamFormat::new())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,4 +272,100 @@ mod tests {
 
         assert_eq!(body, expected_proto_buf);
     }
+
+    #[tokio::test]
+    async fn deserialize_protobuf_stream_format() {
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct TestInputStructure {
+            #[prost(string, tag = "1")]
+            foo1: String,
+            #[prost(string, tag = "2")]
+            foo2: String,
+        }
+
+        let test_stream_vec = vec![
+            TestInputStructure {
+                foo1: "bar1".to_string(),
+                foo2: "bar2".repeat(100)
+            };
+            7
+        ];
+
+        let app = Router::new().route(
+            "/",
+            post(|body: StreamBodyFrom| async {
+                let items: Vec<TestInputStructure> = body.protobuf().try_collect().await.unwrap();
+                StreamBodyAs::protobuf(stream::iter(items))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let request_body: Vec<u8> = test_stream_vec
+            .iter()
+            .flat_map(|obj| obj.encode_length_delimited_to_vec())
+            .collect();
+
+        let request_chunks: Vec<Result<Vec<u8>, std::io::Error>> = request_body
+            .chunks(7)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect();
+
+        let res = client
+            .post("/")
+            .body(reqwest::Body::wrap_stream(stream::iter(request_chunks)))
+            .send()
+            .await
+            .unwrap();
+        let body = res.bytes().await.unwrap().to_vec();
+
+        assert_eq!(body, request_body);
+    }
+
+    #[tokio::test]
+    async fn deserialize_protobuf_stream_format_max_obj_len() {
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct TestInputStructure {
+            #[prost(string, tag = "1")]
+            foo1: String,
+        }
+
+        // Hostile length prefix without the message itself
+        let mut request_body = Vec::new();
+        prost::encoding::encode_varint(u32::MAX as u64, &mut request_body);
+
+        let body = StreamBodyFrom::new(axum::body::Body::from(request_body))
+            .with_options(StreamBodyFromOptions::new().max_obj_len(1024));
+
+        let items: Vec<Result<TestInputStructure, axum::Error>> = body.protobuf().collect().await;
+
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
+    #[tokio::test]
+    async fn deserialize_protobuf_stream_format_overflowing_len() {
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct TestInputStructure {
+            #[prost(string, tag = "1")]
+            foo1: String,
+        }
+
+        let mut request_body = Vec::new();
+        prost::encoding::encode_varint(u64::MAX, &mut request_body);
+
+        // Rejected by the default limit
+        let body = StreamBodyFrom::new(axum::body::Body::from(request_body.clone()));
+        let items: Vec<Result<TestInputStructure, axum::Error>> = body.protobuf().collect().await;
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+
+        // Rejected as an invalid length without the limit
+        let mut options = StreamBodyFromOptions::new();
+        options.max_obj_len = None;
+        let body = StreamBodyFrom::new(axum::body::Body::from(request_body)).with_options(options);
+        let items: Vec<Result<TestInputStructure, axum::Error>> = body.protobuf().collect().await;
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
 }
@@ -0,0 +1,358 @@
+use crate::stream_body_as::StreamBodyAsOptions;
+use crate::stream_format::StreamingFormat;
+use crate::StreamBodyAs;
+use futures::stream::BoxStream;
+use futures::Stream;
+use futures::StreamExt;
+use http::HeaderMap;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Server-Sent Event to send to the client.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SseEvent {
+    data: String,
+    event: Option<String>,
+    id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl SseEvent {
+    pub fn new<D: Into<String>>(data: D) -> Self {
+        Self {
+            data: data.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Create an event with the data serialized as JSON.
+    pub fn json<T: Serialize>(data: &T) -> Result<Self, axum::Error> {
+        serde_json::to_string(data)
+            .map(Self::new)
+            .map_err(axum::Error::new)
+    }
+
+    /// Sets the event type (`event:` field).
+    pub fn with_event<S: Into<String>>(mut self, event: S) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Sets the event ID (`id:` field).
+    pub fn with_id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets the reconnection time (`retry:` field).
+    pub fn with_retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    fn write_to(&self, buf: &mut Vec<u8>) -> Result<(), axum::Error> {
+        fn write_field(buf: &mut Vec<u8>, name: &str, value: &str) -> Result<(), axum::Error> {
+            if value.contains(['\n', '\r']) {
+                return Err(axum::Error::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("SSE {} field must not contain new lines", name),
+                )));
+            }
+            buf.extend_from_slice(name.as_bytes());
+            buf.extend_from_slice(b": ");
+            buf.extend_from_slice(value.as_bytes());
+            buf.push(b'\n');
+            Ok(())
+        }
+
+        if let Some(event) = &self.event {
+            write_field(buf, "event", event)?;
+        }
+        if let Some(id) = &self.id {
+            write_field(buf, "id", id)?;
+        }
+        if let Some(retry) = &self.retry {
+            write_field(buf, "retry", &retry.as_millis().to_string())?;
+        }
+        // The data is sent as several `data:` lines, splitting it on any of the SSE line endings
+        for line in self.data.replace("\r\n", "\n").split(['\r', '\n']) {
+            write_field(buf, "data", line)?;
+        }
+        buf.push(b'\n');
+        Ok(())
+    }
+}
+
+type SseEventMapper<T> = Arc<dyn Fn(T) -> Result<SseEvent, axum::Error> + Send + Sync>;
+
+pub struct SseStreamFormat<T> {
+    mapper: SseEventMapper<T>,
+    event: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl<T> SseStreamFormat<T>
+where
+    T: Serialize,
+{
+    /// Create a format sending each item as an event with the item serialized as JSON data.
+    pub fn new() -> Self {
+        Self::with_mapper(|obj: T| SseEvent::json(&obj))
+    }
+}
+
+impl SseStreamFormat<SseEvent> {
+    /// Create a format sending the events as they are.
+    pub fn events() -> Self {
+        Self::with_mapper(Ok)
+    }
+}
+
+impl<T> SseStreamFormat<T> {
+    /// Create a format converting each item to an event with the specified function.
+    pub fn with_mapper<F>(mapper: F) -> Self
+    where
+        F: Fn(T) -> Result<SseEvent, axum::Error> + Send + Sync + 'static,
+    {
+        Self {
+            mapper: Arc::new(mapper),
+            event: None,
+            retry: None,
+        }
+    }
+
+    /// Sets the default event type for the events without their own.
+    pub fn with_event<S: Into<String>>(mut self, event: S) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Sets the reconnection time sent to the client at the beginning of the stream.
+    pub fn with_retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+impl<T> StreamingFormat<T> for SseStreamFormat<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn to_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<T, axum::Error>>,
+        options: &'a StreamBodyAsOptions,
+    ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
+        let mapper = self.mapper.clone();
+        let default_event = self.event.clone();
+
+        let stream_bytes: BoxStream<Result<axum::body::Bytes, axum::Error>> = Box::pin({
+            options.error_policy_adapter(stream.map(move |obj_res| match obj_res {
+                Err(e) => Err(e),
+                Ok(obj) => {
+                    let mut sse_event = mapper(obj)?;
+                    if sse_event.event.is_none() {
+                        sse_event.event = default_event.clone();
+                    }
+                    let mut buf = Vec::new();
+                    sse_event.write_to(&mut buf)?;
+                    Ok(axum::body::Bytes::from(buf))
+                }
+            }))
+        });
+
+        match self.retry {
+            Some(retry) => {
+                let prepend_stream: BoxStream<Result<axum::body::Bytes, axum::Error>> =
+                    Box::pin(futures::stream::once(futures::future::ready(Ok(
+                        axum::body::Bytes::from(format!("retry: {}\n\n", retry.as_millis())),
+                    ))));
+                Box::pin(prepend_stream.chain(stream_bytes))
+            }
+            None => stream_bytes,
+        }
+    }
+
+    fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
+        let mut header_map = HeaderMap::new();
+        header_map.insert(
+            http::header::CONTENT_TYPE,
+            options
+                .content_type
+                .clone()
+                .unwrap_or_else(|| http::header::HeaderValue::from_static("text/event-stream")),
+        );
+        header_map.insert(
+            http::header::CACHE_CONTROL,
+            http::header::HeaderValue::from_static("no-cache"),
+        );
+        Some(header_map)
+    }
+
+    fn format_name(&self) -> &'static str {
+        "sse"
+    }
+
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        Some(axum::body::Bytes::from_static(b":\n\n"))
+    }
+}
+
+impl<'a> StreamBodyAs<'a> {
+    pub fn sse<S, T>(stream: S) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = T> + 'a + Send,
+    {
+        Self::new(SseStreamFormat::new(), stream.map(Ok::<T, axum::Error>))
+    }
+
+    pub fn sse_with_errors<S, T, E>(stream: S) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = Result<T, E>> + 'a + Send,
+        E: Into<axum::Error>,
+    {
+        Self::new(SseStreamFormat::new(), stream)
+    }
+}
+
+impl StreamBodyAsOptions {
+    pub fn sse<'a, S, T>(self, stream: S) -> StreamBodyAs<'a>
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = T> + 'a + Send,
+    {
+        StreamBodyAs::with_options(
+            SseStreamFormat::new(),
+            stream.map(Ok::<T, axum::Error>),
+            self,
+        )
+    }
+
+    pub fn sse_with_errors<'a, S, T, E>(self, stream: S) -> StreamBodyAs<'a>
+    where
+        T: Serialize + Send + Sync + 'static,
+        S: Stream<Item = Result<T, E>> + 'a + Send,
+        E: Into<axum::Error>,
+    {
+        StreamBodyAs::with_options(SseStreamFormat::new(), stream, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use crate::StreamBodyAs;
+    use axum::{routing::*, Router};
+    use futures::stream;
+
+    #[tokio::test]
+    async fn serialize_sse_stream_format() {
+        #[derive(Debug, Clone, Serialize)]
+        struct TestOutputStructure {
+            foo: String,
+        }
+
+        let test_stream_vec = vec![
+            TestOutputStructure {
+                foo: "bar".to_string()
+            };
+            7
+        ];
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                StreamBodyAs::new(
+                    SseStreamFormat::new()
+                        .with_event("test")
+                        .with_retry(Duration::from_secs(5)),
+                    test_stream.map(Ok::<_, axum::Error>),
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let expected_sse = "retry: 5000\n\n".to_string()
+            + &test_stream_vec
+                .iter()
+                .map(|item| {
+                    format!(
+                        "event: test\ndata: {}\n\n",
+                        serde_json::to_string(item).unwrap()
+                    )
+                })
+                .collect::<String>();
+
+        let res = client.get("/").send().await.unwrap();
+        assert_eq!(
+            res.headers()
+                .get("content-type")
+                .and_then(|h| h.to_str().ok()),
+            Some("text/event-stream")
+        );
+
+        let body = res.text().await.unwrap();
+
+        assert_eq!(body, expected_sse);
+    }
+
+    #[tokio::test]
+    async fn serialize_sse_stream_format_with_mapper() {
+        let test_stream = Box::pin(stream::iter(vec![
+            (1, "first line\nsecond line".to_string()),
+            (2, "third line".to_string()),
+        ]));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                StreamBodyAs::new(
+                    SseStreamFormat::with_mapper(|(id, text): (i32, String)| {
+                        Ok(SseEvent::new(text)
+                            .with_id(id.to_string())
+                            .with_event("text"))
+                    }),
+                    test_stream.map(Ok::<_, axum::Error>),
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap();
+        let body = res.text().await.unwrap();
+
+        assert_eq!(
+            body,
+            "event: text\nid: 1\ndata: first line\ndata: second line\n\nevent: text\nid: 2\ndata: third line\n\n"
+        );
+    }
+
+    #[test]
+    fn serialize_sse_event_line_endings() {
+        let mut buf = Vec::new();
+        SseEvent::new("first\rsecond\r\nthird\nfourth")
+            .write_to(&mut buf)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "data: first\ndata: second\ndata: third\ndata: fourth\n\n"
+        );
+
+        assert!(SseEvent::new("data")
+            .with_event("test\revent")
+            .write_to(&mut Vec::new())
+            .is_err());
+        assert!(SseEvent::new("data")
+            .with_id("test\nid")
+            .write_to(&mut Vec::new())
+            .is_err());
+    }
+}
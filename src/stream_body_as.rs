@@ -1,7 +1,13 @@
+use crate::stream_buffering::{BufferedBytesStream, StreamBufferingPolicy};
 use crate::stream_format::StreamingFormat;
+use crate::stream_lifecycle::{
+    StreamEndReason, StreamItemsCounter, StreamLifecycle, StreamLifecycleHooks, StreamObserver,
+    StreamProgress, StreamSummary,
+};
+use crate::stream_limits::{StreamLimits, StreamLimitsAdapter, StreamLimitsState};
+use crate::stream_pacing::{PacedBytesStream, StreamRateLimiter};
 use axum::body::{Body, HttpBody};
 use axum::response::{IntoResponse, Response};
-use bytes::BytesMut;
 use futures::stream::BoxStream;
 use futures::StreamExt;
 use futures::{Stream, TryStreamExt};
@@ -9,11 +15,16 @@ use http::{HeaderMap, HeaderValue};
 use http_body::Frame;
 use std::fmt::Formatter;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 pub struct StreamBodyAs<'a> {
     stream: BoxStream<'a, Result<Frame<axum::body::Bytes>, axum::Error>>,
     headers: Option<HeaderMap>,
+    lifecycle: Option<StreamLifecycle>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
 }
 
 impl<'a> std::fmt::Debug for StreamBodyAs<'a> {
@@ -43,9 +54,61 @@ impl<'a> StreamBodyAs<'a> {
         S: Stream<Item = Result<T, E>> + 'a + Send,
         E: Into<axum::Error>,
     {
+        let mut headers = stream_format.http_response_headers(&options);
+        #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+        if let Some(compression) = options.compression {
+            let headers = headers.get_or_insert_with(HeaderMap::new);
+            headers.insert(
+                http::header::CONTENT_ENCODING,
+                HeaderValue::from_static(compression.encoding_name()),
+            );
+            headers.insert(
+                http::header::VARY,
+                HeaderValue::from_static("accept-encoding"),
+            );
+        }
+        if options.limits.is_enabled() {
+            if let Ok(limits) = HeaderValue::from_str(&options.limits.header_value()) {
+                headers
+                    .get_or_insert_with(HeaderMap::new)
+                    .insert(STREAM_LIMITS_HEADER, limits);
+            }
+        }
+        if options.trailers {
+            headers
+                .get_or_insert_with(HeaderMap::new)
+                .insert(http::header::TRAILER, stream_trailer_names());
+        }
+        let progress = Arc::new(StreamProgress::default());
+        let mut observers: Vec<Box<dyn StreamObserver>> = Vec::new();
+        if options.lifecycle.is_enabled() {
+            observers.push(Box::new(options.lifecycle.clone()));
+        }
+        #[cfg(feature = "metrics")]
+        observers.push(Box::new(crate::stream_metrics::StreamMetrics::new(
+            stream_format.format_name(),
+        )));
+        #[cfg(feature = "tracing")]
+        let span = {
+            let tracing = crate::stream_tracing::StreamTracing::new(
+                stream_format.format_name(),
+                headers.as_ref(),
+            );
+            let span = tracing.span().clone();
+            observers.push(Box::new(tracing));
+            span
+        };
+        let lifecycle = if observers.is_empty() {
+            None
+        } else {
+            Some(StreamLifecycle::new(observers, progress.clone()))
+        };
         Self {
-            stream: Self::create_stream_frames(&stream_format, stream, &options),
-            headers: stream_format.http_response_headers(&options),
+            stream: Self::create_stream_frames(&stream_format, stream, &options, progress),
+            headers,
+            lifecycle,
+            #[cfg(feature = "tracing")]
+            span,
         }
     }
 
@@ -68,59 +131,126 @@ impl<'a> StreamBodyAs<'a> {
         stream_format: &FMT,
         stream: S,
         options: &StreamBodyAsOptions,
+        progress: Arc<StreamProgress>,
     ) -> BoxStream<'a, Result<Frame<axum::body::Bytes>, axum::Error>>
     where
         FMT: StreamingFormat<T>,
         S: Stream<Item = Result<T, E>> + 'a + Send,
         E: Into<axum::Error>,
     {
-        let boxed_stream = Box::pin(stream.map_err(|e| e.into()));
-        match (options.buffering_ready_items, options.buffering_bytes) {
-            (Some(buffering_ready_items), _) => stream_format
-                .to_bytes_stream(boxed_stream, options)
-                .ready_chunks(buffering_ready_items)
-                .map(|chunks| {
-                    let mut buf = BytesMut::new();
-                    for chunk in chunks {
-                        buf.extend_from_slice(&chunk?);
+        let stream = stream.map_err(|e| e.into());
+        #[cfg(feature = "tracing")]
+        let stream = stream.inspect_err(|e| tracing::warn!(error = %e, "Stream item error"));
+        let boxed_stream = Box::pin(options.error_policy_adapter(Box::pin(stream)));
+
+        // The limits end the stream of items, so the formats write their closing bytes as usual.
+        // The items are counted after the limits, which may read one more item to detect the truncation
+        let limits_state = Arc::new(StreamLimitsState::default());
+        let boxed_stream: BoxStream<'a, Result<T, axum::Error>> = if options.limits.is_enabled() {
+            Box::pin(StreamItemsCounter::new(
+                StreamLimitsAdapter::new(
+                    boxed_stream,
+                    options.limits.clone(),
+                    limits_state.clone(),
+                ),
+                progress.clone(),
+            ))
+        } else {
+            Box::pin(StreamItemsCounter::new(boxed_stream, progress.clone()))
+        };
+        let bytes_stream = stream_format.to_bytes_stream(boxed_stream, options);
+        let bytes_stream = if options.limits.max_bytes.is_some() {
+            let bytes_limits_state = limits_state.clone();
+            bytes_stream
+                .inspect_ok(move |bytes| bytes_limits_state.add_bytes(bytes.len()))
+                .boxed()
+        } else {
+            bytes_stream
+        };
+
+        // The pacing holds the serialized items, while the heartbeats are sent regardless of it
+        let bytes_stream = match &options.rate_limiter {
+            Some(rate_limiter) => PacedBytesStream::new(bytes_stream, rate_limiter.clone()).boxed(),
+            None => bytes_stream,
+        };
+
+        let heartbeat = options
+            .heartbeat_interval
+            .zip(stream_format.heartbeat_bytes());
+        // With the buffering, the heartbeats are sent between the buffered frames at the item boundaries
+        let buffering = options.buffering();
+        let bytes_stream = if buffering.is_enabled() {
+            let buffered_stream = BufferedBytesStream::new(bytes_stream, buffering);
+            match heartbeat {
+                Some((heartbeat_interval, heartbeat_bytes)) => buffered_stream
+                    .with_heartbeat(heartbeat_bytes, heartbeat_interval)
+                    .boxed(),
+                None => buffered_stream.boxed(),
+            }
+        } else {
+            match heartbeat {
+                Some((heartbeat_interval, heartbeat_bytes)) => {
+                    let mut interval = tokio::time::interval_at(
+                        tokio::time::Instant::now() + heartbeat_interval,
+                        heartbeat_interval,
+                    );
+                    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                    tokio_stream::StreamExt::timeout_repeating(bytes_stream, interval)
+                        .map(move |res| res.unwrap_or_else(|_| Ok(heartbeat_bytes.clone())))
+                        .boxed()
+                }
+                None => bytes_stream,
+            }
+        };
+
+        #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+        let bytes_stream = match options.compression {
+            Some(compression) => {
+                crate::stream_compression::compress_bytes_stream(bytes_stream, compression)
+            }
+            None => bytes_stream,
+        };
+
+        if options.trailers {
+            let terminated_error = options.terminated_error.clone();
+            let error_code = options.trailers_error_code.clone();
+            bytes_stream
+                .map(Some)
+                .chain(futures::stream::once(futures::future::ready(None)))
+                .scan((false, 0usize), move |(done, bytes_count), maybe_bytes| {
+                    if *done {
+                        return futures::future::ready(None);
                     }
-                    Ok(Frame::data(buf.freeze()))
+                    futures::future::ready(Some(match maybe_bytes {
+                        Some(Ok(bytes)) => {
+                            *bytes_count += bytes.len();
+                            Ok(Frame::data(bytes))
+                        }
+                        Some(Err(e)) => {
+                            // The response is aborted, so there are no trailers to send
+                            *done = true;
+                            Err(e)
+                        }
+                        None => {
+                            // The error the stream is terminated with by the error policy
+                            *done = true;
+                            let error = terminated_error.lock().unwrap().take();
+                            if let Some(e) = &error {
+                                progress.set_error(e);
+                            }
+                            Ok(Frame::trailers(stream_trailers(
+                                progress.items(),
+                                *bytes_count,
+                                error.as_ref(),
+                                error_code.as_ref(),
+                                limits_state.truncated(),
+                            )))
+                        }
+                    }))
                 })
-                .boxed(),
-            (_, Some(buffering_bytes)) => {
-                let bytes_stream = stream_format.to_bytes_stream(boxed_stream, options).chain(
-                    futures::stream::once(futures::future::ready(Ok(bytes::Bytes::new()))),
-                );
-
-                bytes_stream
-                    .scan(
-                        BytesMut::with_capacity(buffering_bytes),
-                        move |current_buffer, maybe_bytes| {
-                            futures::future::ready(match maybe_bytes {
-                                Ok(bytes) if bytes.is_empty() => {
-                                    Some(vec![Ok(Frame::data(current_buffer.split().freeze()))])
-                                }
-                                Ok(bytes) => {
-                                    let mut frames = Vec::new();
-                                    current_buffer.extend_from_slice(&bytes);
-                                    while current_buffer.len() >= buffering_bytes {
-                                        let buffer =
-                                            current_buffer.split_to(buffering_bytes).freeze();
-                                        frames.push(Ok(Frame::data(buffer)));
-                                    }
-                                    Some(frames)
-                                }
-                                Err(_) => None,
-                            })
-                        },
-                    )
-                    .flat_map(|res| futures::stream::iter(res).boxed())
-                    .boxed()
-            }
-            (None, None) => stream_format
-                .to_bytes_stream(boxed_stream, options)
-                .map(|res| res.map(Frame::data))
-                .boxed(),
+                .boxed()
+        } else {
+            bytes_stream.map(|res| res.map(Frame::data)).boxed()
         }
     }
 }
@@ -141,19 +271,196 @@ impl<'a> HttpBody for StreamBodyAs<'a> {
     type Error = axum::Error;
 
     fn poll_frame(
-        mut self: Pin<&mut Self>,
+        self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
-        Pin::new(&mut self.stream).poll_next(cx)
+        let this = self.get_mut();
+        #[cfg(feature = "tracing")]
+        let _entered = this.span.clone().entered();
+        let polled = Pin::new(&mut this.stream).poll_next(cx);
+        if let Some(lifecycle) = this.lifecycle.as_mut() {
+            lifecycle.observe(&polled);
+        }
+        polled
+    }
+}
+
+impl<'a> Drop for StreamBodyAs<'a> {
+    fn drop(&mut self) {
+        if let Some(lifecycle) = self.lifecycle.as_mut() {
+            lifecycle.finish(StreamEndReason::Cancelled);
+        }
     }
 }
 
 pub type HttpHeaderValue = http::header::HeaderValue;
 
+pub const STREAM_STATUS_TRAILER: &str = "x-stream-status";
+pub const STREAM_ITEMS_TRAILER: &str = "x-stream-items";
+pub const STREAM_BYTES_TRAILER: &str = "x-stream-bytes";
+pub const STREAM_ERROR_MESSAGE_TRAILER: &str = "x-stream-error-message";
+/// The error code mapped with [`StreamBodyAsOptions::trailers_error_code`].
+pub const STREAM_ERROR_CODE_TRAILER: &str = "x-stream-error-code";
+/// The limit the stream is truncated by (`max-items`, `max-bytes` or `max-duration`).
+pub const STREAM_TRUNCATED_TRAILER: &str = "x-stream-truncated";
+/// The header declaring the limits of the response stream.
+pub const STREAM_LIMITS_HEADER: &str = "x-stream-limits";
+
+const STREAM_TRAILERS: [&str; 6] = [
+    STREAM_STATUS_TRAILER,
+    STREAM_ITEMS_TRAILER,
+    STREAM_BYTES_TRAILER,
+    STREAM_ERROR_CODE_TRAILER,
+    STREAM_ERROR_MESSAGE_TRAILER,
+    STREAM_TRUNCATED_TRAILER,
+];
+
+// The value of the `Trailer` header announcing the trailers
+fn stream_trailer_names() -> HeaderValue {
+    HeaderValue::from_str(&STREAM_TRAILERS.join(", "))
+        .unwrap_or_else(|_| HeaderValue::from_static(STREAM_STATUS_TRAILER))
+}
+
+fn stream_trailers(
+    items: usize,
+    bytes: usize,
+    error: Option<&axum::Error>,
+    error_code: Option<&StreamErrorCodeMapper>,
+    truncated: Option<crate::StreamLimitReason>,
+) -> HeaderMap {
+    let mut trailers = HeaderMap::new();
+    trailers.insert(STREAM_ITEMS_TRAILER, HeaderValue::from(items));
+    trailers.insert(STREAM_BYTES_TRAILER, HeaderValue::from(bytes));
+    if let Some(truncated) = truncated {
+        trailers.insert(
+            STREAM_TRUNCATED_TRAILER,
+            HeaderValue::from_static(truncated.as_str()),
+        );
+    }
+    match error {
+        None => {
+            trailers.insert(STREAM_STATUS_TRAILER, HeaderValue::from_static("complete"));
+        }
+        Some(e) => {
+            trailers.insert(STREAM_STATUS_TRAILER, HeaderValue::from_static("error"));
+            if let Some(code) = error_code.and_then(|error_code| error_code(e)) {
+                trailers.insert(STREAM_ERROR_CODE_TRAILER, code);
+            }
+            // Header values can't contain control characters
+            let message: String = e
+                .to_string()
+                .chars()
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .collect();
+            if let Ok(message) = HeaderValue::from_str(&message) {
+                trailers.insert(STREAM_ERROR_MESSAGE_TRAILER, message);
+            }
+        }
+    }
+    trailers
+}
+
+type StreamErrorCallback = Arc<dyn Fn(&axum::Error) + Send + Sync>;
+type StreamErrorCodeMapper = Arc<dyn Fn(&axum::Error) -> Option<HeaderValue> + Send + Sync>;
+// The error the stream is ended with by the `Terminate` policy, reported in the trailers
+type StreamTerminatedError = Arc<Mutex<Option<axum::Error>>>;
+
+/// What to do with the response when the stream produces an error.
+/// The policy applies to the items and their serialization errors before the format writes its framing,
+/// so it works the same for all formats, while the errors in the framing itself abort the response.
+#[derive(Clone)]
+pub enum StreamErrorPolicy {
+    /// Propagate the error to the HTTP server, so the connection is aborted.
+    Abort,
+    /// Drop the failed items, reporting the errors to the callback.
+    Skip(StreamErrorCallback),
+    /// End the stream on the first error, so the format writes its closing bytes
+    /// and the response ends as if the stream is complete (with the trailers, the error is reported there).
+    Terminate,
+}
+
+impl StreamErrorPolicy {
+    pub fn skip<F>(on_error: F) -> Self
+    where
+        F: Fn(&axum::Error) + Send + Sync + 'static,
+    {
+        Self::Skip(Arc::new(on_error))
+    }
+}
+
+impl std::fmt::Debug for StreamErrorPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Abort => write!(f, "Abort"),
+            Self::Skip(_) => write!(f, "Skip"),
+            Self::Terminate => write!(f, "Terminate"),
+        }
+    }
+}
+
+impl Default for StreamErrorPolicy {
+    fn default() -> Self {
+        Self::Abort
+    }
+}
+
+pub(crate) struct StreamErrorPolicyAdapter<S> {
+    stream: S,
+    error_policy: StreamErrorPolicy,
+    terminated_error: StreamTerminatedError,
+    terminated: bool,
+}
+
+impl<S, T> Stream for StreamErrorPolicyAdapter<S>
+where
+    S: Stream<Item = Result<T, axum::Error>> + Unpin,
+{
+    type Item = Result<T, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.terminated {
+                return Poll::Ready(None);
+            }
+            match self.stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Err(e))) => match &self.error_policy {
+                    StreamErrorPolicy::Abort => return Poll::Ready(Some(Err(e))),
+                    StreamErrorPolicy::Skip(on_error) => on_error(&e),
+                    StreamErrorPolicy::Terminate => {
+                        self.terminated = true;
+                        self.terminated_error.lock().unwrap().get_or_insert(e);
+                    }
+                },
+                other => return other,
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.error_policy {
+            StreamErrorPolicy::Abort => self.stream.size_hint(),
+            _ => (0, self.stream.size_hint().1),
+        }
+    }
+}
+
 pub struct StreamBodyAsOptions {
     pub buffering_ready_items: Option<usize>,
     pub buffering_bytes: Option<usize>,
     pub content_type: Option<HttpHeaderValue>,
+    pub heartbeat_interval: Option<Duration>,
+    pub buffering_max_latency: Option<Duration>,
+    pub buffering_min_frame_size: Option<usize>,
+    pub buffering_max_frame_size: Option<usize>,
+    pub error_policy: StreamErrorPolicy,
+    pub trailers: bool,
+    pub limits: StreamLimits,
+    pub rate_limiter: Option<StreamRateLimiter>,
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    pub compression: Option<crate::StreamCompression>,
+    lifecycle: StreamLifecycleHooks,
+    trailers_error_code: Option<StreamErrorCodeMapper>,
+    terminated_error: StreamTerminatedError,
 }
 
 impl StreamBodyAsOptions {
@@ -162,6 +469,19 @@ impl StreamBodyAsOptions {
             buffering_ready_items: None,
             buffering_bytes: None,
             content_type: None,
+            heartbeat_interval: None,
+            buffering_max_latency: None,
+            buffering_min_frame_size: None,
+            buffering_max_frame_size: None,
+            error_policy: StreamErrorPolicy::Abort,
+            trailers: false,
+            limits: StreamLimits::new(),
+            rate_limiter: None,
+            #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+            compression: None,
+            lifecycle: StreamLifecycleHooks::default(),
+            trailers_error_code: None,
+            terminated_error: StreamTerminatedError::default(),
         }
     }
 
@@ -179,14 +499,161 @@ impl StreamBodyAsOptions {
         self.content_type = Some(content_type);
         self
     }
+
+    /// Send a format specific heartbeat when there are no items to send for the specified interval,
+    /// to keep idle connections alive behind proxies.
+    /// It has no effect for the formats that don't support heartbeats.
+    pub fn heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
+        self.heartbeat_interval = Some(heartbeat_interval);
+        self
+    }
+
+    /// Flush the buffered data when it waits longer than the specified latency,
+    /// even if the item or byte thresholds are not reached yet.
+    /// With the latency specified, the item threshold flushes only when the number of items is reached.
+    pub fn buffering_max_latency(mut self, max_latency: Duration) -> Self {
+        self.buffering_max_latency = Some(max_latency);
+        self
+    }
+
+    /// Choose between aborting the response, skipping the failed items and ending the response gracefully
+    /// when the stream produces an error. The buffered data is sent before the error in all cases.
+    pub fn error_policy(mut self, error_policy: StreamErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Send the HTTP trailers with the completion status, the number of items and bytes at the end of the stream.
+    /// The errors are still handled with the error policy: when the stream is ended by [`StreamErrorPolicy::Terminate`],
+    /// the trailers contain the error message, so the clients can tell a complete stream from a truncated one,
+    /// while [`StreamErrorPolicy::Abort`] aborts the response without the trailers.
+    pub fn trailers(mut self, trailers: bool) -> Self {
+        self.trailers = trailers;
+        self
+    }
+
+    /// Map the error the stream is terminated with to the error code sent in the trailers.
+    pub fn trailers_error_code<F>(mut self, error_code: F) -> Self
+    where
+        F: Fn(&axum::Error) -> Option<HeaderValue> + Send + Sync + 'static,
+    {
+        self.trailers_error_code = Some(Arc::new(error_code));
+        self
+    }
+
+    /// Compress each frame of the response with the specified compression.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    pub fn compression(mut self, compression: crate::StreamCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Compress the response with the compression accepted by the client,
+    /// as specified in the request `Accept-Encoding` header.
+    #[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+    pub fn accept_encoding(mut self, accept_encoding: &HeaderValue) -> Self {
+        self.compression = crate::StreamCompression::negotiate(accept_encoding);
+        self
+    }
+
+    /// Replace the buffering thresholds with the specified policy.
+    pub fn buffering_policy(mut self, policy: StreamBufferingPolicy) -> Self {
+        self.buffering_ready_items = policy.ready_items;
+        self.buffering_bytes = policy.bytes;
+        self.buffering_max_latency = policy.max_latency;
+        self.buffering_min_frame_size = policy.min_frame_size;
+        self.buffering_max_frame_size = policy.max_frame_size;
+        self
+    }
+
+    // The adapter applying the error policy, recording the error the stream is terminated with for the trailers
+    pub(crate) fn error_policy_adapter<S>(&self, stream: S) -> StreamErrorPolicyAdapter<S> {
+        StreamErrorPolicyAdapter {
+            stream,
+            error_policy: self.error_policy.clone(),
+            terminated_error: self.terminated_error.clone(),
+            terminated: false,
+        }
+    }
+
+    pub(crate) fn buffering(&self) -> StreamBufferingPolicy {
+        StreamBufferingPolicy {
+            ready_items: self.buffering_ready_items,
+            bytes: self.buffering_bytes,
+            max_latency: self.buffering_max_latency,
+            min_frame_size: self.buffering_min_frame_size,
+            max_frame_size: self.buffering_max_frame_size,
+        }
+    }
+
+    /// Replace the limits of the response with the specified ones.
+    pub fn limits(mut self, limits: StreamLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// End the response after the specified number of items.
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.limits = self.limits.max_items(max_items);
+        self
+    }
+
+    /// End the response when the serialized items reach the specified number of bytes.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.limits = self.limits.max_bytes(max_bytes);
+        self
+    }
+
+    /// End the response when it takes longer than the specified duration.
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.limits = self.limits.max_duration(max_duration);
+        self
+    }
+
+    /// Pace the response with the rate limiter.
+    /// Pass the clones of the same limiter to several responses to cap their aggregate rate.
+    pub fn rate_limiter(mut self, rate_limiter: StreamRateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Call the function with the summary when the stream is complete and all the data is sent to the HTTP server.
+    pub fn on_complete<F>(mut self, on_complete: F) -> Self
+    where
+        F: Fn(&StreamSummary) + Send + Sync + 'static,
+    {
+        self.lifecycle.on_complete = Some(Arc::new(on_complete));
+        self
+    }
+
+    /// Call the function with the summary when the stream ends with an error,
+    /// including the errors reported in the trailers.
+    pub fn on_error<F>(mut self, on_error: F) -> Self
+    where
+        F: Fn(&StreamSummary) + Send + Sync + 'static,
+    {
+        self.lifecycle.on_error = Some(Arc::new(on_error));
+        self
+    }
+
+    /// Call the function with the summary when the response is dropped before the stream ends,
+    /// which happens when the client disconnects.
+    pub fn on_cancel<F>(mut self, on_cancel: F) -> Self
+    where
+        F: Fn(&StreamSummary) + Send + Sync + 'static,
+    {
+        self.lifecycle.on_cancel = Some(Arc::new(on_cancel));
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::TextStreamFormat;
+    use crate::{AnyStreamFormat, TextStreamFormat};
     use bytes::Bytes;
     use futures::TryStreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_stream_body_as_options() {
@@ -267,4 +734,263 @@ mod tests {
         assert_eq!(data[4], Bytes::from("hir"));
         assert_eq!(data[5], Bytes::from("d"));
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_body_as_heartbeat() {
+        let stream = futures::stream::iter(vec!["First".to_string(), "Second".to_string()])
+            .then(|item| async move {
+                if item == "Second" {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                }
+                item
+            })
+            .boxed();
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            stream.map(Ok::<_, axum::Error>),
+            StreamBodyAsOptions::new().heartbeat_interval(Duration::from_millis(50)),
+        );
+        let response = stream_body_as.into_response();
+        let read = response.into_body().into_data_stream();
+        let data: Vec<Bytes> = read.try_collect().await.unwrap();
+        // The heartbeats every 50ms while waiting 200ms for the second item
+        assert_eq!(data.len(), 5);
+        assert_eq!(data[0], Bytes::from("First"));
+        assert_eq!(data[data.len() - 1], Bytes::from("Second"));
+        assert!(data[1..data.len() - 1]
+            .iter()
+            .all(|heartbeat| heartbeat == &Bytes::from("\n")));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_body_as_heartbeat_with_buffering() {
+        let stream = futures::stream::iter(vec!["First".to_string(), "Second".to_string()])
+            .then(|item| async move {
+                if item == "Second" {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                }
+                item
+            })
+            .boxed();
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            stream.map(Ok::<_, axum::Error>),
+            StreamBodyAsOptions::new()
+                .buffering_bytes(4)
+                .heartbeat_interval(Duration::from_millis(50)),
+        );
+        let response = stream_body_as.into_response();
+        let read = response.into_body().into_data_stream();
+        let data: Vec<Bytes> = read.try_collect().await.unwrap();
+        // The rest of the first item is flushed before the heartbeats
+        assert_eq!(data[0], Bytes::from("Firs"));
+        assert_eq!(data[1], Bytes::from("t"));
+        let heartbeats = data[2..]
+            .iter()
+            .take_while(|heartbeat| heartbeat == &&Bytes::from("\n"))
+            .count();
+        assert_eq!(heartbeats, 3);
+        assert_eq!(
+            data[2 + heartbeats..].to_vec(),
+            vec![Bytes::from("Seco"), Bytes::from("nd")]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_body_as_buffering_max_latency() {
+        let stream = futures::stream::iter(vec![
+            "First".to_string(),
+            "Second".to_string(),
+            "Third".to_string(),
+        ])
+        .then(|item| async move {
+            if item == "Third" {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+            item
+        })
+        .boxed();
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            stream.map(Ok::<_, axum::Error>),
+            StreamBodyAsOptions::new()
+                .buffering_bytes(1024)
+                .buffering_max_latency(Duration::from_millis(50)),
+        );
+        let response = stream_body_as.into_response();
+        let read = response.into_body().into_data_stream();
+        let data: Vec<Bytes> = read.try_collect().await.unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0], Bytes::from("FirstSecond"));
+        assert_eq!(data[1], Bytes::from("Third"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_body_as_buffering_bytes_errors() {
+        let test_stream = || {
+            futures::stream::iter(vec![
+                Ok("First".to_string()),
+                Ok("Second".to_string()),
+                Err(axum::Error::new("test error")),
+                Ok("Third".to_string()),
+            ])
+        };
+
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            test_stream(),
+            StreamBodyAsOptions::new().buffering_bytes(1024),
+        );
+        let mut read = stream_body_as
+            .into_response()
+            .into_body()
+            .into_data_stream();
+        assert_eq!(
+            read.next().await.unwrap().unwrap(),
+            Bytes::from("FirstSecond")
+        );
+        assert!(read.next().await.unwrap().is_err());
+
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            test_stream(),
+            StreamBodyAsOptions::new()
+                .buffering_bytes(1024)
+                .error_policy(StreamErrorPolicy::Terminate),
+        );
+        let read = stream_body_as
+            .into_response()
+            .into_body()
+            .into_data_stream();
+        let data: Vec<Bytes> = read.try_collect().await.unwrap();
+        assert_eq!(data, vec![Bytes::from("FirstSecond")]);
+    }
+
+    async fn poll_frames(
+        mut stream_body_as: StreamBodyAs<'_>,
+    ) -> Vec<Result<Frame<Bytes>, axum::Error>> {
+        let mut frames = Vec::new();
+        while let Some(frame) =
+            futures::future::poll_fn(|cx| Pin::new(&mut stream_body_as).poll_frame(cx)).await
+        {
+            frames.push(frame);
+        }
+        frames
+    }
+
+    #[tokio::test]
+    async fn test_stream_body_as_trailers() {
+        let test_stream = || {
+            futures::stream::iter(vec![
+                Ok("First".to_string()),
+                Ok("Second".to_string()),
+                Err(axum::Error::new("test\nerror")),
+                Ok("Third".to_string()),
+            ])
+        };
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            test_stream(),
+            StreamBodyAsOptions::new()
+                .trailers(true)
+                .error_policy(StreamErrorPolicy::Terminate)
+                .trailers_error_code(|_| Some(HeaderValue::from_static("test-code"))),
+        );
+        assert_eq!(
+            stream_body_as
+                .headers
+                .as_ref()
+                .and_then(|headers| headers.get(http::header::TRAILER)),
+            Some(&HeaderValue::from_static(
+                "x-stream-status, x-stream-items, x-stream-bytes, x-stream-error-code, x-stream-error-message, x-stream-truncated"
+            ))
+        );
+
+        let frames: Vec<Frame<Bytes>> = poll_frames(stream_body_as)
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].data_ref(), Some(&Bytes::from("First")));
+        assert_eq!(frames[1].data_ref(), Some(&Bytes::from("Second")));
+        let trailers = frames[2].trailers_ref().unwrap();
+        assert_eq!(trailers[STREAM_STATUS_TRAILER], "error");
+        assert_eq!(trailers[STREAM_ITEMS_TRAILER], "2");
+        assert_eq!(trailers[STREAM_BYTES_TRAILER], "11");
+        assert_eq!(trailers[STREAM_ERROR_CODE_TRAILER], "test-code");
+        assert_eq!(trailers[STREAM_ERROR_MESSAGE_TRAILER], "test error");
+
+        // The stream with the skipped errors is complete
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            test_stream(),
+            StreamBodyAsOptions::new()
+                .trailers(true)
+                .error_policy(StreamErrorPolicy::skip(|_| {})),
+        );
+        let frames = poll_frames(stream_body_as).await;
+        assert_eq!(frames.len(), 4);
+        let trailers = frames[3].as_ref().unwrap().trailers_ref().unwrap();
+        assert_eq!(trailers[STREAM_STATUS_TRAILER], "complete");
+        assert_eq!(trailers[STREAM_ITEMS_TRAILER], "3");
+
+        // The aborted response has no trailers
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            test_stream(),
+            StreamBodyAsOptions::new().trailers(true),
+        );
+        let frames = poll_frames(stream_body_as).await;
+        assert_eq!(frames.len(), 3);
+        assert!(frames[2].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stream_body_as_error_policy_skip() {
+        let skipped_errors = Arc::new(AtomicUsize::new(0));
+        let skipped_errors_counter = skipped_errors.clone();
+
+        let stream = futures::stream::iter(vec![
+            Ok("First".to_string()),
+            Err(axum::Error::new("test error")),
+            Ok("Second".to_string()),
+        ]);
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            stream,
+            StreamBodyAsOptions::new().error_policy(StreamErrorPolicy::skip(move |_| {
+                skipped_errors_counter.fetch_add(1, Ordering::Relaxed);
+            })),
+        );
+        let read = stream_body_as
+            .into_response()
+            .into_body()
+            .into_data_stream();
+        let data: Vec<Bytes> = read.try_collect().await.unwrap();
+        assert_eq!(data, vec![Bytes::from("First"), Bytes::from("Second")]);
+        assert_eq!(skipped_errors.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_body_as_any_format() {
+        let stream_formats: Vec<AnyStreamFormat<String>> = vec![
+            AnyStreamFormat::new(TextStreamFormat::new()),
+            AnyStreamFormat::new(Box::new(TextStreamFormat::new())),
+            AnyStreamFormat::new(Arc::new(TextStreamFormat::new())),
+        ];
+
+        for stream_format in stream_formats {
+            let stream = futures::stream::iter(vec!["First".to_string(), "Second".to_string()]);
+            let response =
+                StreamBodyAs::new(stream_format, stream.map(Ok::<_, axum::Error>)).into_response();
+            assert_eq!(
+                response.headers().get(http::header::CONTENT_TYPE).unwrap(),
+                "text/plain; charset=utf-8"
+            );
+            let read = response.into_body().into_data_stream();
+            let data: Vec<Bytes> = read.try_collect().await.unwrap();
+            assert_eq!(data, vec![Bytes::from("First"), Bytes::from("Second")]);
+        }
+    }
 }
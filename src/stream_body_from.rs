@@ -0,0 +1,82 @@
+use crate::stream_format::StreamingDecodeFormat;
+use axum::body::Body;
+use axum::extract::{FromRequest, Request};
+use futures::stream::BoxStream;
+use std::convert::Infallible;
+use std::fmt::Formatter;
+
+/// Extractor for streaming request bodies.
+///
+/// It keeps the request body as is, so you can decode it incrementally as a stream of your objects
+/// in the specified format instead of buffering the whole body in memory.
+pub struct StreamBodyFrom {
+    body: Body,
+    options: StreamBodyFromOptions,
+}
+
+impl std::fmt::Debug for StreamBodyFrom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "StreamBodyFrom")
+    }
+}
+
+impl StreamBodyFrom {
+    pub fn new(body: Body) -> Self {
+        Self {
+            body,
+            options: StreamBodyFromOptions::new(),
+        }
+    }
+
+    pub fn with_options(mut self, options: StreamBodyFromOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Decode the body as a stream of your objects in the specified format.
+    pub fn decode<T, FMT>(self, stream_format: FMT) -> BoxStream<'static, Result<T, axum::Error>>
+    where
+        FMT: StreamingDecodeFormat<T>,
+    {
+        stream_format.decode_bytes_stream(Box::pin(self.body.into_data_stream()), &self.options)
+    }
+
+    #[cfg(any(feature = "json", feature = "arrow"))]
+    pub(crate) fn into_parts(self) -> (Body, StreamBodyFromOptions) {
+        (self.body, self.options)
+    }
+}
+
+impl<S> FromRequest<S> for StreamBodyFrom
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request(req: Request, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::new(req.into_body()))
+    }
+}
+
+/// The default limit of a single encoded object in the request stream (16 MiB).
+pub const DEFAULT_MAX_OBJ_LEN: usize = 16 * 1024 * 1024;
+
+pub struct StreamBodyFromOptions {
+    /// The limit of a single encoded object, [`DEFAULT_MAX_OBJ_LEN`] by default.
+    /// Setting it to `None` disables the limit, so the client controls how much is buffered.
+    pub max_obj_len: Option<usize>,
+}
+
+impl StreamBodyFromOptions {
+    pub fn new() -> Self {
+        Self {
+            max_obj_len: Some(DEFAULT_MAX_OBJ_LEN),
+        }
+    }
+
+    /// Limit the size of a single encoded object in the stream.
+    pub fn max_obj_len(mut self, max_obj_len: usize) -> Self {
+        self.max_obj_len = Some(max_obj_len);
+        self
+    }
+}
@@ -0,0 +1,446 @@
+use bytes::BytesMut;
+use futures::stream::BoxStream;
+use futures::{Future, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Buffering policy for the HTTP frames produced from the stream.
+///
+/// The thresholds can be combined and the buffer is flushed on whichever is hit first:
+/// - `ready_items`: the number of items is buffered, or there are no more items ready to send
+///   (the latter only when `max_latency` isn't specified);
+/// - `bytes`: the number of bytes is buffered, the frames are split to this size exactly;
+/// - `max_latency`: the buffered data waits longer than the specified duration.
+///
+/// Additionally, `min_frame_size` holds the item based flushes until enough data is buffered
+/// (the latency and the end of the stream flush anyway), and `max_frame_size` splits the larger frames.
+/// Without any of thresholds specified, each item is sent as a frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamBufferingPolicy {
+    pub ready_items: Option<usize>,
+    pub bytes: Option<usize>,
+    pub max_latency: Option<Duration>,
+    pub min_frame_size: Option<usize>,
+    pub max_frame_size: Option<usize>,
+}
+
+impl StreamBufferingPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ready_items(mut self, ready_items: usize) -> Self {
+        self.ready_items = Some(ready_items);
+        self
+    }
+
+    pub fn bytes(mut self, bytes: usize) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    pub fn max_latency(mut self, max_latency: Duration) -> Self {
+        self.max_latency = Some(max_latency);
+        self
+    }
+
+    pub fn min_frame_size(mut self, min_frame_size: usize) -> Self {
+        self.min_frame_size = Some(min_frame_size);
+        self
+    }
+
+    pub fn max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = Some(max_frame_size);
+        self
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self != &Self::default()
+    }
+}
+
+/// Buffers the serialized items into frames according to the buffering policy.
+///
+/// The heartbeats are sent from here as separate frames, since the buffered frames may end in the middle
+/// of an item. When there are no frames to send for the heartbeat interval, the buffered data
+/// (that always ends at an item boundary) is flushed before the heartbeat.
+pub(crate) struct BufferedBytesStream<'a> {
+    stream: BoxStream<'a, Result<axum::body::Bytes, axum::Error>>,
+    policy: StreamBufferingPolicy,
+    buffer: BytesMut,
+    buffered_items: usize,
+    deadline: Option<Pin<Box<tokio::time::Sleep>>>,
+    heartbeat: Option<(axum::body::Bytes, tokio::time::Interval)>,
+    ready_frames: VecDeque<axum::body::Bytes>,
+    pending_error: Option<axum::Error>,
+    finished: bool,
+}
+
+impl<'a> BufferedBytesStream<'a> {
+    pub(crate) fn new(
+        stream: BoxStream<'a, Result<axum::body::Bytes, axum::Error>>,
+        mut policy: StreamBufferingPolicy,
+    ) -> Self {
+        // Zero bytes threshold can't be reached, so it is ignored like zero `max_frame_size`
+        policy.bytes = policy.bytes.filter(|bytes| *bytes > 0);
+        Self {
+            stream,
+            buffer: BytesMut::with_capacity(policy.bytes.unwrap_or_default()),
+            policy,
+            buffered_items: 0,
+            deadline: None,
+            heartbeat: None,
+            ready_frames: VecDeque::new(),
+            pending_error: None,
+            finished: false,
+        }
+    }
+
+    pub(crate) fn with_heartbeat(
+        mut self,
+        heartbeat_bytes: axum::body::Bytes,
+        heartbeat_interval: Duration,
+    ) -> Self {
+        let mut interval = tokio::time::interval_at(
+            tokio::time::Instant::now() + heartbeat_interval,
+            heartbeat_interval,
+        );
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        self.heartbeat = Some((heartbeat_bytes, interval));
+        self
+    }
+
+    fn has_min_frame_size(&self) -> bool {
+        self.buffer.len() >= self.policy.min_frame_size.unwrap_or_default()
+    }
+
+    fn push_bytes(&mut self, bytes: axum::body::Bytes) {
+        if self.buffer.is_empty() {
+            if let Some(max_latency) = self.policy.max_latency {
+                self.deadline = Some(Box::pin(tokio::time::sleep(max_latency)));
+            }
+        }
+        self.buffer.extend_from_slice(&bytes);
+        self.buffered_items += 1;
+
+        if let Some(buffering_bytes) = self.policy.bytes {
+            while self.buffer.len() >= buffering_bytes {
+                let frame = self.buffer.split_to(buffering_bytes).freeze();
+                self.push_frame(frame);
+            }
+        }
+
+        let items_flush = match self.policy.ready_items {
+            Some(ready_items) => self.buffered_items >= ready_items,
+            None => self.policy.bytes.is_none() && self.policy.max_latency.is_none(),
+        };
+
+        if items_flush && self.has_min_frame_size() || self.buffer.is_empty() {
+            self.flush();
+        }
+    }
+
+    fn push_frame(&mut self, mut frame: axum::body::Bytes) {
+        if let Some(max_frame_size) = self.policy.max_frame_size.filter(|size| *size > 0) {
+            while frame.len() > max_frame_size {
+                self.ready_frames.push_back(frame.split_to(max_frame_size));
+            }
+        }
+        self.ready_frames.push_back(frame);
+    }
+
+    fn flush(&mut self) {
+        if !self.buffer.is_empty() {
+            let frame = self.buffer.split().freeze();
+            self.push_frame(frame);
+        }
+        self.buffered_items = 0;
+        self.deadline = None;
+    }
+}
+
+impl<'a> Stream for BufferedBytesStream<'a> {
+    type Item = Result<axum::body::Bytes, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(frame) = self.ready_frames.pop_front() {
+                if let Some((_, interval)) = self.heartbeat.as_mut() {
+                    interval.reset();
+                }
+                return Poll::Ready(Some(Ok(frame)));
+            }
+
+            if let Some(e) = self.pending_error.take() {
+                return Poll::Ready(Some(Err(e)));
+            }
+
+            if self.finished {
+                return Poll::Ready(None);
+            }
+
+            match self.stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(bytes))) => self.push_bytes(bytes),
+                Poll::Ready(Some(Err(e))) => {
+                    // Send what was buffered before the error, so the client receives everything produced
+                    self.finished = true;
+                    self.pending_error = Some(e);
+                    self.flush();
+                }
+                Poll::Ready(None) => {
+                    self.finished = true;
+                    self.flush();
+                }
+                Poll::Pending => {
+                    let deadline_elapsed = self
+                        .deadline
+                        .as_mut()
+                        .map_or(false, |deadline| deadline.as_mut().poll(cx).is_ready());
+                    let ready_flush = self.policy.ready_items.is_some()
+                        && self.policy.max_latency.is_none()
+                        && self.has_min_frame_size();
+
+                    if deadline_elapsed || ready_flush {
+                        self.flush();
+                    }
+                    if self.ready_frames.is_empty() {
+                        let heartbeat = self.heartbeat.as_mut().and_then(|(bytes, interval)| {
+                            interval.poll_tick(cx).is_ready().then(|| bytes.clone())
+                        });
+                        if let Some(heartbeat) = heartbeat {
+                            self.flush();
+                            self.ready_frames.push_back(heartbeat);
+                        }
+                    }
+                    if self.ready_frames.is_empty() {
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StreamBodyAs, StreamBodyAsOptions, TextStreamFormat};
+    use axum::response::IntoResponse;
+    use bytes::Bytes;
+    use futures::TryStreamExt;
+
+    async fn buffered_frames(policy: StreamBufferingPolicy, delayed: bool) -> Vec<Bytes> {
+        let stream = futures::stream::iter(vec![
+            "First".to_string(),
+            "Second".to_string(),
+            "Third".to_string(),
+        ])
+        .then(move |item| async move {
+            if delayed && item == "Third" {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+            Ok::<_, axum::Error>(item)
+        });
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            stream,
+            StreamBodyAsOptions::new().buffering_policy(policy),
+        );
+        let read = stream_body_as
+            .into_response()
+            .into_body()
+            .into_data_stream();
+        read.try_collect().await.unwrap()
+    }
+
+    // Sends the items after the delays in milliseconds, returning the frames and whether the body ended with error
+    async fn timed_frames(
+        policy: StreamBufferingPolicy,
+        items: Vec<(u64, Result<&'static str, &'static str>)>,
+    ) -> (Vec<Bytes>, bool) {
+        let stream = futures::stream::iter(items).then(|(delay, item)| async move {
+            if delay > 0 {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+            }
+            item.map(|item| item.to_string()).map_err(axum::Error::new)
+        });
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            stream,
+            StreamBodyAsOptions::new().buffering_policy(policy),
+        );
+        let mut read = stream_body_as
+            .into_response()
+            .into_body()
+            .into_data_stream();
+        let mut frames = Vec::new();
+        while let Some(frame) = read.next().await {
+            match frame {
+                Ok(frame) => frames.push(frame),
+                Err(_) => return (frames, true),
+            }
+        }
+        (frames, false)
+    }
+
+    fn expected_frames(frames: &[&str]) -> Vec<Bytes> {
+        frames
+            .iter()
+            .map(|frame| Bytes::from(frame.to_string()))
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_buffering_zero_bytes() {
+        let frames = tokio::time::timeout(
+            Duration::from_secs(5),
+            buffered_frames(StreamBufferingPolicy::new().bytes(0), false),
+        )
+        .await
+        .unwrap();
+        assert_eq!(frames, expected_frames(&["First", "Second", "Third"]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_buffering_combined_thresholds() {
+        let policy = StreamBufferingPolicy::new()
+            .bytes(8)
+            .ready_items(2)
+            .max_latency(Duration::from_millis(50));
+        let (frames, errored) = timed_frames(
+            policy,
+            vec![
+                (0, Ok("First")),
+                (0, Ok("Second")),
+                (0, Ok("Third")),
+                (200, Ok("Fourth")),
+                (0, Ok("Fifth")),
+            ],
+        )
+        .await;
+        assert!(!errored);
+        // Bytes split, items flush, latency flush, bytes split and items flush again
+        assert_eq!(
+            frames,
+            expected_frames(&["FirstSec", "ond", "Third", "FourthFi", "fth"])
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_buffering_bytes_with_frame_sizes() {
+        let frames = buffered_frames(
+            StreamBufferingPolicy::new().bytes(8).max_frame_size(3),
+            false,
+        )
+        .await;
+        assert_eq!(
+            frames,
+            expected_frames(&["Fir", "stS", "ec", "ond", "Thi", "rd"])
+        );
+
+        let frames = buffered_frames(
+            StreamBufferingPolicy::new()
+                .bytes(4)
+                .ready_items(1)
+                .min_frame_size(6),
+            false,
+        )
+        .await;
+        assert_eq!(frames, expected_frames(&["Firs", "tSec", "ondT", "hird"]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_buffering_error_after_buffered_data() {
+        let policy = StreamBufferingPolicy::new()
+            .ready_items(10)
+            .max_frame_size(4);
+        let (frames, errored) = timed_frames(
+            policy,
+            vec![(0, Ok("First")), (0, Ok("Second")), (0, Err("test error"))],
+        )
+        .await;
+        assert!(errored);
+        assert_eq!(frames, expected_frames(&["Firs", "tSec", "ond"]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_buffering_policy_matrix() {
+        let test_cases: Vec<(StreamBufferingPolicy, bool, Vec<&str>)> = vec![
+            (
+                StreamBufferingPolicy::new(),
+                false,
+                vec!["First", "Second", "Third"],
+            ),
+            (
+                StreamBufferingPolicy::new().ready_items(2),
+                false,
+                vec!["FirstSecond", "Third"],
+            ),
+            (
+                StreamBufferingPolicy::new().ready_items(10),
+                true,
+                vec!["FirstSecond", "Third"],
+            ),
+            (
+                StreamBufferingPolicy::new().bytes(3),
+                false,
+                vec!["Fir", "stS", "eco", "ndT", "hir", "d"],
+            ),
+            (
+                StreamBufferingPolicy::new().ready_items(2).bytes(8),
+                false,
+                vec!["FirstSec", "ond", "Third"],
+            ),
+            (
+                StreamBufferingPolicy::new()
+                    .bytes(1024)
+                    .max_latency(Duration::from_millis(50)),
+                true,
+                vec!["FirstSecond", "Third"],
+            ),
+            (
+                StreamBufferingPolicy::new()
+                    .ready_items(10)
+                    .max_latency(Duration::from_millis(50)),
+                true,
+                vec!["FirstSecond", "Third"],
+            ),
+            (
+                StreamBufferingPolicy::new().min_frame_size(8),
+                false,
+                vec!["FirstSecond", "Third"],
+            ),
+            (
+                StreamBufferingPolicy::new()
+                    .ready_items(10)
+                    .min_frame_size(16),
+                true,
+                vec!["FirstSecondThird"],
+            ),
+            (
+                StreamBufferingPolicy::new().max_frame_size(4),
+                false,
+                vec!["Firs", "t", "Seco", "nd", "Thir", "d"],
+            ),
+            (
+                StreamBufferingPolicy::new()
+                    .ready_items(3)
+                    .max_frame_size(6),
+                false,
+                vec!["FirstS", "econdT", "hird"],
+            ),
+        ];
+
+        for (policy, delayed, expected) in test_cases {
+            let frames = buffered_frames(policy.clone(), delayed).await;
+            assert_eq!(
+                frames,
+                expected.into_iter().map(Bytes::from).collect::<Vec<_>>(),
+                "{:?}",
+                policy
+            );
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use http::HeaderValue;
+use std::io::Write;
+
+/// Compression of the HTTP frames.
+///
+/// Each frame is compressed and flushed separately, so the clients can decode the output incrementally
+/// without waiting for the whole response (which isn't the case for the usual compression layers buffering the data).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamCompression {
+    #[cfg(feature = "gzip")]
+    Gzip,
+    #[cfg(feature = "brotli")]
+    Brotli,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl StreamCompression {
+    // In the order of preference for the same quality values
+    const SUPPORTED: &'static [StreamCompression] = &[
+        #[cfg(feature = "zstd")]
+        StreamCompression::Zstd,
+        #[cfg(feature = "brotli")]
+        StreamCompression::Brotli,
+        #[cfg(feature = "gzip")]
+        StreamCompression::Gzip,
+    ];
+
+    /// The name of the encoding for `Content-Encoding` header.
+    pub fn encoding_name(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "gzip")]
+            StreamCompression::Gzip => "gzip",
+            #[cfg(feature = "brotli")]
+            StreamCompression::Brotli => "br",
+            #[cfg(feature = "zstd")]
+            StreamCompression::Zstd => "zstd",
+        }
+    }
+
+    /// Choose the compression from the request `Accept-Encoding` header value,
+    /// taking into account the quality values.
+    /// Returns `None` when none of the enabled compressions is accepted.
+    pub fn negotiate(accept_encoding: &HeaderValue) -> Option<StreamCompression> {
+        let accept_encoding = accept_encoding.to_str().ok()?;
+        let accepted = crate::stream_negotiation::quality_values(accept_encoding);
+
+        let quality_of = |compression: &StreamCompression| {
+            accepted
+                .iter()
+                .find(|(name, _)| name == compression.encoding_name())
+                .or_else(|| accepted.iter().find(|(name, _)| name == "*"))
+                .map(|(_, quality)| *quality)
+                .unwrap_or(0.0)
+        };
+
+        let mut selected: Option<(StreamCompression, f32)> = None;
+        for compression in Self::SUPPORTED {
+            let quality = quality_of(compression);
+            if quality > 0.0
+                && selected.map_or(true, |(_, selected_quality)| quality > selected_quality)
+            {
+                selected = Some((*compression, quality));
+            }
+        }
+        selected.map(|(compression, _)| compression)
+    }
+
+    fn encoder(&self) -> std::io::Result<StreamEncoder> {
+        match *self {
+            #[cfg(feature = "gzip")]
+            StreamCompression::Gzip => Ok(StreamEncoder::Gzip(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            ))),
+            #[cfg(feature = "brotli")]
+            StreamCompression::Brotli => Ok(StreamEncoder::Brotli(Box::new(
+                brotli::CompressorWriter::new(
+                    Vec::new(),
+                    BROTLI_BUFFER_SIZE,
+                    BROTLI_QUALITY,
+                    BROTLI_LGWIN,
+                ),
+            ))),
+            #[cfg(feature = "zstd")]
+            StreamCompression::Zstd => Ok(StreamEncoder::Zstd(zstd::stream::write::Encoder::new(
+                Vec::new(),
+                zstd::DEFAULT_COMPRESSION_LEVEL,
+            )?)),
+        }
+    }
+}
+
+#[cfg(feature = "brotli")]
+const BROTLI_BUFFER_SIZE: usize = 4096;
+// The lower quality is a better fit for streaming, when the data is compressed on the fly
+#[cfg(feature = "brotli")]
+const BROTLI_QUALITY: u32 = 5;
+#[cfg(feature = "brotli")]
+const BROTLI_LGWIN: u32 = 22;
+
+enum StreamEncoder {
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    #[cfg(feature = "brotli")]
+    Brotli(Box<brotli::CompressorWriter<Vec<u8>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+}
+
+impl StreamEncoder {
+    fn encode_frame(&mut self, bytes: &[u8]) -> std::io::Result<axum::body::Bytes> {
+        match self {
+            #[cfg(feature = "gzip")]
+            StreamEncoder::Gzip(encoder) => {
+                encoder.write_all(bytes)?;
+                encoder.flush()?;
+                Ok(std::mem::take(encoder.get_mut()).into())
+            }
+            #[cfg(feature = "brotli")]
+            StreamEncoder::Brotli(encoder) => {
+                encoder.write_all(bytes)?;
+                encoder.flush()?;
+                Ok(std::mem::take(encoder.get_mut()).into())
+            }
+            #[cfg(feature = "zstd")]
+            StreamEncoder::Zstd(encoder) => {
+                encoder.write_all(bytes)?;
+                encoder.flush()?;
+                Ok(std::mem::take(encoder.get_mut()).into())
+            }
+        }
+    }
+
+    fn finish(self) -> std::io::Result<axum::body::Bytes> {
+        match self {
+            #[cfg(feature = "gzip")]
+            StreamEncoder::Gzip(encoder) => encoder.finish().map(|buf| buf.into()),
+            #[cfg(feature = "brotli")]
+            StreamEncoder::Brotli(encoder) => Ok(encoder.into_inner().into()),
+            #[cfg(feature = "zstd")]
+            StreamEncoder::Zstd(encoder) => encoder.finish().map(|buf| buf.into()),
+        }
+    }
+}
+
+pub(crate) fn compress_bytes_stream(
+    bytes_stream: BoxStream<'_, Result<axum::body::Bytes, axum::Error>>,
+    compression: StreamCompression,
+) -> BoxStream<'_, Result<axum::body::Bytes, axum::Error>> {
+    let encoder = match compression.encoder() {
+        Ok(encoder) => encoder,
+        Err(e) => {
+            return futures::stream::once(futures::future::ready(Err(axum::Error::new(e)))).boxed()
+        }
+    };
+
+    bytes_stream
+        .map(Some)
+        .chain(futures::stream::once(futures::future::ready(None)))
+        .scan(Some(encoder), |encoder, maybe_bytes| {
+            futures::future::ready(match maybe_bytes {
+                Some(Err(e)) => Some(Err(e)),
+                Some(Ok(bytes)) => encoder
+                    .as_mut()
+                    .map(|encoder| encoder.encode_frame(&bytes).map_err(axum::Error::new)),
+                None => encoder
+                    .take()
+                    .map(|encoder| encoder.finish().map_err(axum::Error::new)),
+            })
+        })
+        .boxed()
+}
+
+#[cfg(all(test, feature = "gzip", feature = "brotli", feature = "zstd"))]
+mod tests {
+    use super::*;
+    use crate::{StreamBodyAs, StreamBodyAsOptions, TextStreamFormat};
+    use axum::response::IntoResponse;
+    use bytes::Bytes;
+    use futures::TryStreamExt;
+
+    #[test]
+    fn test_negotiate_compression() {
+        assert_eq!(
+            StreamCompression::negotiate(&HeaderValue::from_static("gzip, br;q=0.5")),
+            Some(StreamCompression::Gzip)
+        );
+        assert_eq!(
+            StreamCompression::negotiate(&HeaderValue::from_static("gzip;q=0.5, br, zstd")),
+            Some(StreamCompression::Zstd)
+        );
+        assert_eq!(
+            StreamCompression::negotiate(&HeaderValue::from_static("gzip;q=0, deflate")),
+            None
+        );
+        assert_eq!(
+            StreamCompression::negotiate(&HeaderValue::from_static("*;q=0.1, br;q=0.2")),
+            Some(StreamCompression::Brotli)
+        );
+    }
+
+    async fn compressed_frames(compression: StreamCompression) -> Vec<Bytes> {
+        let stream = futures::stream::iter(vec!["First".to_string(), "Second".to_string()]);
+        let response = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            stream.map(Ok::<_, axum::Error>),
+            StreamBodyAsOptions::new().compression(compression),
+        )
+        .into_response();
+        assert_eq!(
+            response.headers().get(http::header::CONTENT_ENCODING),
+            Some(&HeaderValue::from_static(compression.encoding_name()))
+        );
+        assert_eq!(
+            response.headers().get(http::header::VARY),
+            Some(&HeaderValue::from_static("accept-encoding"))
+        );
+        let read = response.into_body().into_data_stream();
+        read.try_collect().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stream_compression_frames() {
+        let frames = compressed_frames(StreamCompression::Gzip).await;
+
+        // Each frame is decodable without waiting for the rest of the stream
+        let mut decoder = flate2::write::GzDecoder::new(Vec::new());
+        decoder.write_all(&frames[0]).unwrap();
+        decoder.flush().unwrap();
+        assert_eq!(decoder.get_ref().as_slice(), b"First");
+        for frame in &frames[1..] {
+            decoder.write_all(frame).unwrap();
+        }
+        assert_eq!(decoder.finish().unwrap(), b"FirstSecond");
+
+        let frames = compressed_frames(StreamCompression::Brotli).await.concat();
+        let mut decoded = Vec::new();
+        std::io::Read::read_to_end(
+            &mut brotli::Decompressor::new(frames.as_slice(), 4096),
+            &mut decoded,
+        )
+        .unwrap();
+        assert_eq!(decoded, b"FirstSecond");
+
+        let frames = compressed_frames(StreamCompression::Zstd).await.concat();
+        assert_eq!(zstd::decode_all(frames.as_slice()).unwrap(), b"FirstSecond");
+    }
+}
@@ -1,6 +1,7 @@
-use crate::StreamBodyAsOptions;
+use crate::{StreamBodyAsOptions, StreamBodyFromOptions};
 use futures::stream::BoxStream;
 use http::HeaderMap;
+use std::sync::Arc;
 
 pub trait StreamingFormat<T> {
     fn to_bytes_stream<'a, 'b>(
@@ -10,4 +11,121 @@ pub trait StreamingFormat<T> {
     ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>>;
 
     fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap>;
+
+    /// Bytes to send to keep the connection alive when there are no items to send for a while.
+    /// The formats that can't express it without changing the content return `None`.
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        None
+    }
+
+    /// The short name of the format, such as `json_array` or `csv`,
+    /// to tell the formats apart in the metrics and traces.
+    fn format_name(&self) -> &'static str {
+        "custom"
+    }
+}
+
+impl<T, F> StreamingFormat<T> for Box<F>
+where
+    F: StreamingFormat<T> + ?Sized,
+{
+    fn to_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<T, axum::Error>>,
+        options: &'a StreamBodyAsOptions,
+    ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
+        (**self).to_bytes_stream(stream, options)
+    }
+
+    fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
+        (**self).http_response_headers(options)
+    }
+
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        (**self).heartbeat_bytes()
+    }
+
+    fn format_name(&self) -> &'static str {
+        (**self).format_name()
+    }
+}
+
+impl<T, F> StreamingFormat<T> for Arc<F>
+where
+    F: StreamingFormat<T> + ?Sized,
+{
+    fn to_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<T, axum::Error>>,
+        options: &'a StreamBodyAsOptions,
+    ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
+        (**self).to_bytes_stream(stream, options)
+    }
+
+    fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
+        (**self).http_response_headers(options)
+    }
+
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        (**self).heartbeat_bytes()
+    }
+
+    fn format_name(&self) -> &'static str {
+        (**self).format_name()
+    }
+}
+
+/// Type-erased stream format, so the format can be chosen at runtime
+/// (from the configuration or the request) and passed around as a value.
+pub struct AnyStreamFormat<T> {
+    inner: Arc<dyn StreamingFormat<T> + Send + Sync>,
+}
+
+impl<T> AnyStreamFormat<T> {
+    pub fn new<F>(stream_format: F) -> Self
+    where
+        F: StreamingFormat<T> + Send + Sync + 'static,
+    {
+        Self {
+            inner: Arc::new(stream_format),
+        }
+    }
+}
+
+impl<T> Clone for AnyStreamFormat<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> StreamingFormat<T> for AnyStreamFormat<T> {
+    fn to_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<T, axum::Error>>,
+        options: &'a StreamBodyAsOptions,
+    ) -> BoxStream<'b, Result<axum::body::Bytes, axum::Error>> {
+        self.inner.to_bytes_stream(stream, options)
+    }
+
+    fn http_response_headers(&self, options: &StreamBodyAsOptions) -> Option<HeaderMap> {
+        self.inner.http_response_headers(options)
+    }
+
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        self.inner.heartbeat_bytes()
+    }
+
+    fn format_name(&self) -> &'static str {
+        self.inner.format_name()
+    }
+}
+
+pub trait StreamingDecodeFormat<T> {
+    fn decode_bytes_stream<'a, 'b>(
+        &'a self,
+        stream: BoxStream<'b, Result<axum::body::Bytes, axum::Error>>,
+        options: &'a StreamBodyFromOptions,
+    ) -> BoxStream<'b, Result<T, axum::Error>>;
 }
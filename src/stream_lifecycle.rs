@@ -0,0 +1,353 @@
+use futures::{Stream, StreamExt};
+use http_body::Frame;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// How the response stream ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEndReason {
+    /// The stream is complete and all the data is sent to the HTTP server.
+    Complete,
+    /// The stream is ended with the error (with the error message).
+    Error(String),
+    /// The response is dropped before the stream is complete, usually because the client disconnected.
+    Cancelled,
+}
+
+impl StreamEndReason {
+    #[cfg(any(feature = "metrics", feature = "tracing"))]
+    pub(crate) fn status(&self) -> &'static str {
+        match self {
+            StreamEndReason::Complete => "complete",
+            StreamEndReason::Error(_) => "error",
+            StreamEndReason::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// The summary of the response stream passed to the lifecycle callbacks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamSummary {
+    /// The number of items read from the stream.
+    pub items: usize,
+    /// The number of bytes sent as the response body (after the compression, if enabled).
+    pub bytes: usize,
+    /// The time since the response is created.
+    pub duration: Duration,
+    pub reason: StreamEndReason,
+}
+
+pub(crate) type StreamSummaryCallback = Arc<dyn Fn(&StreamSummary) + Send + Sync>;
+
+/// Lifecycle callbacks for the response stream.
+#[derive(Clone, Default)]
+pub(crate) struct StreamLifecycleHooks {
+    pub(crate) on_complete: Option<StreamSummaryCallback>,
+    pub(crate) on_error: Option<StreamSummaryCallback>,
+    pub(crate) on_cancel: Option<StreamSummaryCallback>,
+}
+
+impl StreamLifecycleHooks {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.on_complete.is_some() || self.on_error.is_some() || self.on_cancel.is_some()
+    }
+}
+
+impl StreamObserver for StreamLifecycleHooks {
+    fn on_finish(&mut self, summary: &StreamSummary) {
+        let callback = match summary.reason {
+            StreamEndReason::Complete => &self.on_complete,
+            StreamEndReason::Error(_) => &self.on_error,
+            StreamEndReason::Cancelled => &self.on_cancel,
+        };
+        if let Some(callback) = callback {
+            callback(summary);
+        }
+    }
+}
+
+/// The progress of the stream shared with the stream pipeline:
+/// the items read from the stream and the error reported in the trailers.
+#[derive(Default)]
+pub(crate) struct StreamProgress {
+    items: AtomicUsize,
+    error: Mutex<Option<String>>,
+}
+
+impl StreamProgress {
+    pub(crate) fn add_item(&self) {
+        self.items.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn items(&self) -> usize {
+        self.items.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_error(&self, error: &axum::Error) {
+        *self.error.lock().unwrap() = Some(error.to_string());
+    }
+
+    fn take_error(&self) -> Option<String> {
+        self.error.lock().unwrap().take()
+    }
+}
+
+/// Counts the items passed to the format in the progress.
+pub(crate) struct StreamItemsCounter<S> {
+    stream: S,
+    progress: Arc<StreamProgress>,
+}
+
+impl<S> StreamItemsCounter<S> {
+    pub(crate) fn new(stream: S, progress: Arc<StreamProgress>) -> Self {
+        Self { stream, progress }
+    }
+}
+
+impl<S, T> Stream for StreamItemsCounter<S>
+where
+    S: Stream<Item = Result<T, axum::Error>> + Unpin,
+{
+    type Item = Result<T, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = self.stream.poll_next_unpin(cx);
+        if let Poll::Ready(Some(Ok(_))) = &polled {
+            self.progress.add_item();
+        }
+        polled
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+/// Receives the events of the response stream from [`StreamLifecycle`].
+pub(crate) trait StreamObserver: Send {
+    /// New items are read from the stream, `first` is set for the first ones.
+    fn on_items(&mut self, _new_items: usize, _first: bool, _elapsed: Duration) {}
+
+    /// A data frame is sent, `first` is set for the first one.
+    fn on_data(&mut self, _data_len: usize, _first: bool, _elapsed: Duration) {}
+
+    /// The stream is ended, called exactly once.
+    fn on_finish(&mut self, _summary: &StreamSummary) {}
+}
+
+/// Tracks the frames polled from the response and reports them to the observers
+/// (the lifecycle callbacks, metrics and tracing), finishing exactly once:
+/// on the end of the stream, on the error, or when the response is dropped before that.
+pub(crate) struct StreamLifecycle {
+    observers: Vec<Box<dyn StreamObserver>>,
+    progress: Arc<StreamProgress>,
+    reported_items: usize,
+    bytes_count: usize,
+    data_sent: bool,
+    started_at: Instant,
+    finished: bool,
+}
+
+impl StreamLifecycle {
+    pub(crate) fn new(
+        observers: Vec<Box<dyn StreamObserver>>,
+        progress: Arc<StreamProgress>,
+    ) -> Self {
+        Self {
+            observers,
+            progress,
+            reported_items: 0,
+            bytes_count: 0,
+            data_sent: false,
+            started_at: Instant::now(),
+            finished: false,
+        }
+    }
+
+    pub(crate) fn observe(
+        &mut self,
+        polled: &Poll<Option<Result<Frame<axum::body::Bytes>, axum::Error>>>,
+    ) {
+        match polled {
+            Poll::Ready(Some(Ok(frame))) => {
+                self.report_items();
+                if let Some(data) = frame.data_ref() {
+                    let first = !self.data_sent;
+                    self.data_sent = true;
+                    self.bytes_count += data.len();
+                    let elapsed = self.started_at.elapsed();
+                    for observer in self.observers.iter_mut() {
+                        observer.on_data(data.len(), first, elapsed);
+                    }
+                } else if frame.is_trailers() {
+                    // With the trailers enabled, the errors are reported there instead
+                    if let Some(error) = self.progress.take_error() {
+                        self.finish(StreamEndReason::Error(error));
+                    }
+                }
+            }
+            Poll::Ready(Some(Err(e))) => self.finish(StreamEndReason::Error(e.to_string())),
+            Poll::Ready(None) => self.finish(StreamEndReason::Complete),
+            Poll::Pending => {}
+        }
+    }
+
+    fn report_items(&mut self) {
+        let items = self.progress.items();
+        if items > self.reported_items {
+            let first = self.reported_items == 0;
+            let new_items = items - self.reported_items;
+            self.reported_items = items;
+            let elapsed = self.started_at.elapsed();
+            for observer in self.observers.iter_mut() {
+                observer.on_items(new_items, first, elapsed);
+            }
+        }
+    }
+
+    pub(crate) fn finish(&mut self, reason: StreamEndReason) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        self.report_items();
+
+        let summary = StreamSummary {
+            items: self.reported_items,
+            bytes: self.bytes_count,
+            duration: self.started_at.elapsed(),
+            reason,
+        };
+        for observer in self.observers.iter_mut() {
+            observer.on_finish(&summary);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "text"))]
+mod tests {
+    use super::*;
+    use crate::{StreamBodyAs, StreamBodyAsOptions, TextStreamFormat};
+    use axum::body::HttpBody;
+    use axum::response::IntoResponse;
+    use futures::StreamExt;
+    use std::sync::Mutex;
+
+    fn recorded_summaries() -> (
+        Arc<Mutex<Vec<StreamSummary>>>,
+        impl Fn(&StreamSummary) + Send + Sync + Clone + 'static,
+    ) {
+        let summaries = Arc::new(Mutex::new(Vec::new()));
+        let recorder = summaries.clone();
+        (summaries, move |summary: &StreamSummary| {
+            recorder.lock().unwrap().push(summary.clone())
+        })
+    }
+
+    #[tokio::test]
+    async fn test_stream_lifecycle_complete_and_error() {
+        let (summaries, record) = recorded_summaries();
+        let stream_body_as = StreamBodyAsOptions::new()
+            .on_complete(record.clone())
+            .on_error(record.clone())
+            .on_cancel(record.clone())
+            .text(futures::stream::iter(vec![
+                "First".to_string(),
+                "Second".to_string(),
+            ]));
+        let mut body = stream_body_as
+            .into_response()
+            .into_body()
+            .into_data_stream();
+        while body.next().await.is_some() {}
+        drop(body);
+
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            futures::stream::iter(vec![
+                Ok("First".to_string()),
+                Err(axum::Error::new("test error")),
+            ]),
+            StreamBodyAsOptions::new()
+                .on_complete(record.clone())
+                .on_error(record.clone())
+                .on_cancel(record),
+        );
+        let mut body = stream_body_as
+            .into_response()
+            .into_body()
+            .into_data_stream();
+        while body.next().await.is_some() {}
+        drop(body);
+
+        let summaries = summaries.lock().unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].reason, StreamEndReason::Complete);
+        assert_eq!(summaries[0].items, 2);
+        assert_eq!(summaries[0].bytes, 11);
+        assert_eq!(
+            summaries[1].reason,
+            StreamEndReason::Error("test error".to_string())
+        );
+        assert_eq!(summaries[1].items, 1);
+        assert_eq!(summaries[1].bytes, 5);
+    }
+
+    #[tokio::test]
+    async fn test_stream_lifecycle_error_in_trailers() {
+        let (summaries, record) = recorded_summaries();
+        let stream_body_as = StreamBodyAs::with_options(
+            TextStreamFormat::new(),
+            futures::stream::iter(vec![
+                Ok("First".to_string()),
+                Err(axum::Error::new("test error")),
+            ]),
+            StreamBodyAsOptions::new()
+                .trailers(true)
+                .error_policy(crate::StreamErrorPolicy::Terminate)
+                .on_complete(record.clone())
+                .on_error(record),
+        );
+        let mut body = stream_body_as.into_response().into_body();
+        while futures::future::poll_fn(|cx| std::pin::Pin::new(&mut body).poll_frame(cx))
+            .await
+            .is_some()
+        {}
+        drop(body);
+
+        let summaries = summaries.lock().unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(
+            summaries[0].reason,
+            StreamEndReason::Error("test error".to_string())
+        );
+        assert_eq!(summaries[0].items, 1);
+        assert_eq!(summaries[0].bytes, 5);
+    }
+
+    #[tokio::test]
+    async fn test_stream_lifecycle_cancel() {
+        let (summaries, record) = recorded_summaries();
+        let stream_body_as = StreamBodyAsOptions::new()
+            .on_complete(record.clone())
+            .on_cancel(record)
+            .text(
+                futures::stream::iter(vec!["First".to_string()]).chain(futures::stream::pending()),
+            );
+        let mut body = stream_body_as
+            .into_response()
+            .into_body()
+            .into_data_stream();
+        assert!(body.next().await.is_some());
+        drop(body);
+
+        let summaries = summaries.lock().unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].reason, StreamEndReason::Cancelled);
+        assert_eq!(summaries[0].items, 1);
+        assert_eq!(summaries[0].bytes, 5);
+    }
+}
@@ -0,0 +1,320 @@
+use futures::{Future, Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Sleep;
+
+/// Limits for the response stream.
+///
+/// When a limit is reached, the stream ends as if the source is complete,
+/// so the format writes its closing bytes (such as `]` for JSON arrays):
+/// - `max_items`: the number of items read from the stream;
+/// - `max_bytes`: the number of serialized bytes (before the compression). The limit is checked between the items,
+///   so the response may exceed it by the size of the last item and the closing bytes;
+/// - `max_duration`: the time since the response is created.
+///
+/// The truncation is reported in [`crate::STREAM_TRUNCATED_TRAILER`] trailer when the trailers are enabled
+/// (only if the source has more items after the limit, which is checked by reading one more item),
+/// and the limits are declared in advance with [`crate::STREAM_LIMITS_HEADER`] header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamLimits {
+    pub max_items: Option<usize>,
+    pub max_bytes: Option<usize>,
+    pub max_duration: Option<Duration>,
+}
+
+impl StreamLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.max_items.is_some() || self.max_bytes.is_some() || self.max_duration.is_some()
+    }
+
+    // The value for the header declaring the limits, such as `max-items=100, max-duration=30`
+    pub(crate) fn header_value(&self) -> String {
+        let mut limits = Vec::new();
+        if let Some(max_items) = self.max_items {
+            limits.push(format!("max-items={}", max_items));
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            limits.push(format!("max-bytes={}", max_bytes));
+        }
+        if let Some(max_duration) = self.max_duration {
+            limits.push(format!("max-duration={}", max_duration.as_secs_f64()));
+        }
+        limits.join(", ")
+    }
+}
+
+/// The limit the response stream is truncated by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamLimitReason {
+    MaxItems,
+    MaxBytes,
+    MaxDuration,
+}
+
+impl StreamLimitReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamLimitReason::MaxItems => "max-items",
+            StreamLimitReason::MaxBytes => "max-bytes",
+            StreamLimitReason::MaxDuration => "max-duration",
+        }
+    }
+}
+
+/// The serialized bytes and the truncation reason shared between the stages of the response stream.
+#[derive(Default)]
+pub(crate) struct StreamLimitsState {
+    bytes: AtomicUsize,
+    truncated: Mutex<Option<StreamLimitReason>>,
+}
+
+impl StreamLimitsState {
+    pub(crate) fn add_bytes(&self, bytes: usize) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn truncated(&self) -> Option<StreamLimitReason> {
+        *self.truncated.lock().unwrap()
+    }
+
+    fn truncate(&self, reason: StreamLimitReason) {
+        *self.truncated.lock().unwrap() = Some(reason);
+    }
+}
+
+/// Ends the stream of items when any of the limits is reached.
+pub(crate) struct StreamLimitsAdapter<S> {
+    stream: S,
+    limits: StreamLimits,
+    state: Arc<StreamLimitsState>,
+    items: usize,
+    deadline: Option<Pin<Box<Sleep>>>,
+    finished: bool,
+}
+
+impl<S> StreamLimitsAdapter<S> {
+    pub(crate) fn new(stream: S, limits: StreamLimits, state: Arc<StreamLimitsState>) -> Self {
+        let deadline = limits
+            .max_duration
+            .map(|max_duration| Box::pin(tokio::time::sleep(max_duration)));
+        Self {
+            stream,
+            limits,
+            state,
+            items: 0,
+            deadline,
+            finished: false,
+        }
+    }
+
+    fn truncate(&mut self, reason: StreamLimitReason) -> Poll<Option<S::Item>>
+    where
+        S: Stream,
+    {
+        self.finished = true;
+        self.state.truncate(reason);
+        Poll::Ready(None)
+    }
+}
+
+impl<S, T> Stream for StreamLimitsAdapter<S>
+where
+    S: Stream<Item = Result<T, axum::Error>> + Unpin,
+{
+    type Item = Result<T, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+        if self
+            .deadline
+            .as_mut()
+            .map_or(false, |deadline| deadline.as_mut().poll(cx).is_ready())
+        {
+            return self.truncate(StreamLimitReason::MaxDuration);
+        }
+
+        let reached = if self.limits.max_items.map_or(false, |max| self.items >= max) {
+            Some(StreamLimitReason::MaxItems)
+        } else if self
+            .limits
+            .max_bytes
+            .map_or(false, |max| self.state.bytes.load(Ordering::Relaxed) >= max)
+        {
+            Some(StreamLimitReason::MaxBytes)
+        } else {
+            None
+        };
+        if let Some(reason) = reached {
+            // The stream is truncated only when the source has more items after the limit,
+            // otherwise it is complete
+            return match self.stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(_)) => self.truncate(reason),
+                Poll::Ready(None) => {
+                    self.finished = true;
+                    Poll::Ready(None)
+                }
+                Poll::Pending => Poll::Pending,
+            };
+        }
+
+        match self.stream.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(item))) => {
+                self.items += 1;
+                Poll::Ready(Some(Ok(item)))
+            }
+            Poll::Ready(None) => {
+                self.finished = true;
+                Poll::Ready(None)
+            }
+            other => other,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.stream.size_hint();
+        match self.limits.max_items {
+            Some(max_items)
+                if self.limits.max_bytes.is_none() && self.limits.max_duration.is_none() =>
+            {
+                let remaining = max_items.saturating_sub(self.items);
+                (
+                    lower.min(remaining),
+                    Some(upper.map_or(remaining, |upper| upper.min(remaining))),
+                )
+            }
+            _ => (0, upper),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use crate::{
+        StreamBodyAsOptions, STREAM_ITEMS_TRAILER, STREAM_LIMITS_HEADER, STREAM_STATUS_TRAILER,
+        STREAM_TRUNCATED_TRAILER,
+    };
+    use axum::body::HttpBody;
+    use axum::{routing::*, Router};
+    use futures::stream;
+
+    #[tokio::test]
+    async fn test_stream_limits() {
+        let app = Router::new()
+            .route(
+                "/items",
+                get(|| async {
+                    StreamBodyAsOptions::new()
+                        .max_items(3)
+                        .json_array(stream::iter(0..10))
+                }),
+            )
+            .route(
+                "/bytes",
+                get(|| async {
+                    StreamBodyAsOptions::new()
+                        .max_bytes(4)
+                        .json_array(stream::iter(10..20))
+                }),
+            )
+            .route(
+                "/duration",
+                get(|| async {
+                    StreamBodyAsOptions::new()
+                        .max_duration(Duration::from_millis(100))
+                        .json_array(stream::iter(0..3).chain(stream::pending()))
+                }),
+            );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/items").send().await.unwrap();
+        assert_eq!(res.headers()[STREAM_LIMITS_HEADER], "max-items=3");
+        assert_eq!(res.text().await.unwrap(), "[0,1,2]");
+
+        let res = client.get("/bytes").send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "[10,11]");
+
+        let res = client.get("/duration").send().await.unwrap();
+        assert_eq!(res.headers()[STREAM_LIMITS_HEADER], "max-duration=0.1");
+        assert_eq!(res.text().await.unwrap(), "[0,1,2]");
+    }
+
+    #[tokio::test]
+    async fn test_stream_limits_truncated_trailer() {
+        let mut stream_body_as = StreamBodyAsOptions::new()
+            .limits(StreamLimits::new().max_items(2).max_bytes(1024))
+            .trailers(true)
+            .json_nl(stream::iter(0..10));
+
+        let mut frames = Vec::new();
+        while let Some(frame) =
+            futures::future::poll_fn(|cx| Pin::new(&mut stream_body_as).poll_frame(cx)).await
+        {
+            frames.push(frame.unwrap());
+        }
+
+        assert_eq!(frames.len(), 3);
+        let trailers = frames[2].trailers_ref().unwrap();
+        assert_eq!(trailers[STREAM_STATUS_TRAILER], "complete");
+        assert_eq!(trailers[STREAM_ITEMS_TRAILER], "2");
+        assert_eq!(trailers[STREAM_TRUNCATED_TRAILER], "max-items");
+    }
+
+    #[tokio::test]
+    async fn test_stream_limits_reached_by_complete_stream() {
+        let test_cases = vec![
+            (StreamLimits::new().max_items(3), "3"),
+            // The last item crosses the limit
+            (StreamLimits::new().max_bytes(5), "3"),
+        ];
+        for (limits, expected_items) in test_cases {
+            let mut stream_body_as = StreamBodyAsOptions::new()
+                .limits(limits.clone())
+                .trailers(true)
+                .json_nl(stream::iter(0..3));
+
+            let mut frames = Vec::new();
+            while let Some(frame) =
+                futures::future::poll_fn(|cx| Pin::new(&mut stream_body_as).poll_frame(cx)).await
+            {
+                frames.push(frame.unwrap());
+            }
+
+            assert_eq!(frames.len(), 4, "{:?}", limits);
+            let trailers = frames[3].trailers_ref().unwrap();
+            assert_eq!(trailers[STREAM_STATUS_TRAILER], "complete");
+            assert_eq!(trailers[STREAM_ITEMS_TRAILER], expected_items);
+            assert!(
+                trailers.get(STREAM_TRUNCATED_TRAILER).is_none(),
+                "{:?}",
+                limits
+            );
+        }
+    }
+}
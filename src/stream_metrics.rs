@@ -0,0 +1,126 @@
+use crate::stream_lifecycle::{StreamObserver, StreamSummary};
+use std::time::Duration;
+
+/// Counter of the items read from the streams.
+pub const STREAM_ITEMS_METRIC: &str = "axum_streams_items_total";
+/// Counter of the bytes sent in the response bodies.
+pub const STREAM_BYTES_METRIC: &str = "axum_streams_bytes_total";
+/// Histogram of the response frame sizes in bytes.
+pub const STREAM_FRAME_SIZE_METRIC: &str = "axum_streams_frame_size_bytes";
+/// Histogram of the time from the response creation to the first data frame in seconds.
+pub const STREAM_FIRST_BYTE_METRIC: &str = "axum_streams_time_to_first_byte_seconds";
+/// Histogram of the response durations in seconds, labeled with the `status`.
+pub const STREAM_DURATION_METRIC: &str = "axum_streams_duration_seconds";
+
+const FORMAT_LABEL: &str = "format";
+const STATUS_LABEL: &str = "status";
+
+/// Records the metrics of the response frames labeled with the format name.
+/// The duration is recorded with `complete`, `error` or `cancelled` status when the stream ends
+/// or the response is dropped.
+pub(crate) struct StreamMetrics {
+    format_name: &'static str,
+}
+
+impl StreamMetrics {
+    pub(crate) fn new(format_name: &'static str) -> Self {
+        Self { format_name }
+    }
+}
+
+impl StreamObserver for StreamMetrics {
+    fn on_items(&mut self, new_items: usize, _first: bool, _elapsed: Duration) {
+        metrics::counter!(STREAM_ITEMS_METRIC, FORMAT_LABEL => self.format_name)
+            .increment(new_items as u64);
+    }
+
+    fn on_data(&mut self, data_len: usize, first: bool, elapsed: Duration) {
+        if first {
+            metrics::histogram!(STREAM_FIRST_BYTE_METRIC, FORMAT_LABEL => self.format_name)
+                .record(elapsed.as_secs_f64());
+        }
+        metrics::counter!(STREAM_BYTES_METRIC, FORMAT_LABEL => self.format_name)
+            .increment(data_len as u64);
+        metrics::histogram!(STREAM_FRAME_SIZE_METRIC, FORMAT_LABEL => self.format_name)
+            .record(data_len as f64);
+    }
+
+    fn on_finish(&mut self, summary: &StreamSummary) {
+        metrics::histogram!(
+            STREAM_DURATION_METRIC,
+            FORMAT_LABEL => self.format_name,
+            STATUS_LABEL => summary.reason.status()
+        )
+        .record(summary.duration.as_secs_f64());
+    }
+}
+
+#[cfg(all(test, feature = "text"))]
+mod tests {
+    use super::*;
+    use crate::StreamBodyAs;
+    use axum::response::IntoResponse;
+    use futures::StreamExt;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    type RecordedMetric = (String, Vec<(String, String)>, DebugValue);
+
+    #[test]
+    fn test_stream_metrics() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            let stream_body_as = StreamBodyAs::text(futures::stream::iter(vec![
+                "First".to_string(),
+                "Second".to_string(),
+            ]));
+            let body = stream_body_as
+                .into_response()
+                .into_body()
+                .into_data_stream();
+            futures::executor::block_on(body.collect::<Vec<_>>());
+        });
+
+        let metrics: Vec<RecordedMetric> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let (_, key) = key.into_parts();
+                let labels = key
+                    .labels()
+                    .map(|label| (label.key().to_string(), label.value().to_string()))
+                    .collect();
+                (key.name().to_string(), labels, value)
+            })
+            .collect();
+        let find_metric = |name: &str| {
+            metrics
+                .iter()
+                .find(|(metric_name, _, _)| metric_name == name)
+                .unwrap()
+        };
+
+        let (_, labels, items) = find_metric(STREAM_ITEMS_METRIC);
+        assert_eq!(labels, &vec![("format".to_string(), "text".to_string())]);
+        assert_eq!(items, &DebugValue::Counter(2));
+
+        let (_, _, bytes) = find_metric(STREAM_BYTES_METRIC);
+        assert_eq!(bytes, &DebugValue::Counter(11));
+
+        match find_metric(STREAM_FRAME_SIZE_METRIC) {
+            (_, _, DebugValue::Histogram(frame_sizes)) => {
+                assert_eq!(
+                    frame_sizes.iter().map(|size| size.0).collect::<Vec<_>>(),
+                    vec![5.0, 6.0]
+                );
+            }
+            other => panic!("Unexpected frame size metric: {:?}", other),
+        }
+
+        let (_, labels, _) = find_metric(STREAM_DURATION_METRIC);
+        assert!(labels.contains(&("status".to_string(), "complete".to_string())));
+        find_metric(STREAM_FIRST_BYTE_METRIC);
+    }
+}
@@ -0,0 +1,400 @@
+use crate::stream_format::StreamingFormat;
+use crate::{StreamBodyAs, StreamBodyAsOptions};
+use axum::extract::FromRequestParts;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use http::request::Parts;
+use http::{HeaderValue, StatusCode};
+use std::convert::Infallible;
+
+/// Extractor to choose the stream format for the response by the request `Accept` header
+/// (taking into account the quality values) or `?format=` query parameter.
+///
+/// The query parameter has a precedence and matches the names of the formats:
+/// ```rust,ignore
+/// async fn handler(negotiation: StreamFormatNegotiation) -> impl IntoResponse {
+///     negotiation
+///         .format("json", JsonArrayStreamFormat::new())
+///         .format("ndjson", JsonNewLineStreamFormat::new().with_content_type(JsonNewLineContentType::NdJson))
+///         .format("csv", CsvStreamFormat::new(true, b','))
+///         .negotiate(source_test_stream())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StreamFormatNegotiation {
+    accept: Option<HeaderValue>,
+    format_param: Option<String>,
+}
+
+impl StreamFormatNegotiation {
+    pub fn new(accept: Option<HeaderValue>, format_param: Option<String>) -> Self {
+        Self {
+            accept,
+            format_param,
+        }
+    }
+
+    /// Register the first format to choose from. It is also used when the request doesn't specify the format.
+    pub fn format<'a, T, FMT>(self, name: &str, stream_format: FMT) -> StreamBodyAsNegotiator<'a, T>
+    where
+        T: 'a,
+        FMT: StreamingFormat<T> + Send + 'a,
+    {
+        StreamBodyAsNegotiator {
+            negotiation: self,
+            candidates: Vec::new(),
+            options: StreamBodyAsOptions::new(),
+        }
+        .format(name, stream_format)
+    }
+}
+
+impl<S> FromRequestParts<S> for StreamFormatNegotiation
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let format_param = parts.uri.query().and_then(|query| {
+            query.split('&').find_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                (percent_decode(key) == "format").then(|| percent_decode(value))
+            })
+        });
+        Ok(Self::new(
+            parts.headers.get(http::header::ACCEPT).cloned(),
+            format_param,
+        ))
+    }
+}
+
+// The registered format, so its content type is resolved with the options of the response
+trait NegotiableFormat<'a, T>: Send {
+    fn content_type(&self, options: &StreamBodyAsOptions) -> Option<String>;
+
+    fn into_stream_body(
+        self: Box<Self>,
+        stream: BoxStream<'a, Result<T, axum::Error>>,
+        options: StreamBodyAsOptions,
+    ) -> StreamBodyAs<'a>;
+}
+
+impl<'a, T, FMT> NegotiableFormat<'a, T> for FMT
+where
+    T: 'a,
+    FMT: StreamingFormat<T> + Send + 'a,
+{
+    fn content_type(&self, options: &StreamBodyAsOptions) -> Option<String> {
+        self.http_response_headers(options)
+            .and_then(|headers| headers.get(http::header::CONTENT_TYPE).cloned())
+            .and_then(|content_type| content_type.to_str().ok().map(media_type))
+    }
+
+    fn into_stream_body(
+        self: Box<Self>,
+        stream: BoxStream<'a, Result<T, axum::Error>>,
+        options: StreamBodyAsOptions,
+    ) -> StreamBodyAs<'a> {
+        StreamBodyAs::with_options(*self, stream, options)
+    }
+}
+
+struct StreamFormatCandidate<'a, T> {
+    name: String,
+    stream_format: Box<dyn NegotiableFormat<'a, T> + 'a>,
+}
+
+/// The formats to choose from for the response. Created by [`StreamFormatNegotiation::format`].
+pub struct StreamBodyAsNegotiator<'a, T> {
+    negotiation: StreamFormatNegotiation,
+    candidates: Vec<StreamFormatCandidate<'a, T>>,
+    options: StreamBodyAsOptions,
+}
+
+impl<'a, T> StreamBodyAsNegotiator<'a, T>
+where
+    T: 'a,
+{
+    /// Register a format to choose from.
+    /// The media type to match with `Accept` header is taken from the format `Content-Type`
+    /// with the options of the response.
+    pub fn format<FMT>(mut self, name: &str, stream_format: FMT) -> Self
+    where
+        FMT: StreamingFormat<T> + Send + 'a,
+    {
+        self.candidates.push(StreamFormatCandidate {
+            name: name.to_string(),
+            stream_format: Box::new(stream_format),
+        });
+        self
+    }
+
+    /// Options for the response in the chosen format.
+    pub fn options(mut self, options: StreamBodyAsOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Create the response with the chosen format or `406 Not Acceptable` status when none matches.
+    pub fn negotiate<S>(self, stream: S) -> Result<StreamBodyAs<'a>, StatusCode>
+    where
+        S: Stream<Item = T> + 'a + Send,
+    {
+        self.negotiate_with_errors(stream.map(Ok::<T, axum::Error>))
+    }
+
+    pub fn negotiate_with_errors<S, E>(self, stream: S) -> Result<StreamBodyAs<'a>, StatusCode>
+    where
+        S: Stream<Item = Result<T, E>> + 'a + Send,
+        E: Into<axum::Error>,
+    {
+        let selected = self.select().ok_or(StatusCode::NOT_ACCEPTABLE)?;
+        let candidate = self
+            .candidates
+            .into_iter()
+            .nth(selected)
+            .ok_or(StatusCode::NOT_ACCEPTABLE)?;
+
+        let stream_body = candidate.stream_format.into_stream_body(
+            Box::pin(stream.map(|res| res.map_err(|e| e.into()))),
+            self.options,
+        );
+        Ok(stream_body.header(http::header::VARY, HeaderValue::from_static("accept")))
+    }
+
+    fn select(&self) -> Option<usize> {
+        if let Some(format_param) = &self.negotiation.format_param {
+            return self
+                .candidates
+                .iter()
+                .position(|candidate| &candidate.name == format_param);
+        }
+
+        let accept = match self
+            .negotiation
+            .accept
+            .as_ref()
+            .and_then(|accept| accept.to_str().ok())
+            .filter(|accept| !accept.trim().is_empty())
+        {
+            Some(accept) => accept,
+            None => return (!self.candidates.is_empty()).then(|| 0),
+        };
+
+        let media_ranges = quality_values(accept);
+
+        let mut selected: Option<(usize, f32)> = None;
+        for (index, candidate) in self.candidates.iter().enumerate() {
+            let quality = candidate
+                .stream_format
+                .content_type(&self.options)
+                .and_then(|content_type| accepted_quality(&media_ranges, &content_type))
+                .unwrap_or(0.0);
+            if quality > 0.0
+                && selected.map_or(true, |(_, selected_quality)| quality > selected_quality)
+            {
+                selected = Some((index, quality));
+            }
+        }
+        selected.map(|(index, _)| index)
+    }
+}
+
+/// Parses the values with the quality weights (`q` parameter) of `Accept` like headers,
+/// such as `text/plain;q=0.5, application/json` or `gzip;q=0.5, br`.
+/// The values are lowercased without the other parameters, the quality is 1 by default.
+pub(crate) fn quality_values(header_value: &str) -> Vec<(String, f32)> {
+    header_value
+        .split(',')
+        .filter_map(|value| {
+            let mut parts = value.split(';');
+            let value = parts.next()?.trim().to_ascii_lowercase();
+            let quality = parts
+                .find_map(|param| {
+                    let (key, value) = param.split_once('=')?;
+                    if key.trim() == "q" {
+                        value.trim().parse::<f32>().ok()
+                    } else {
+                        None
+                    }
+                })
+                .unwrap_or(1.0);
+            (!value.is_empty()).then(|| (value, quality))
+        })
+        .collect()
+}
+
+// Decodes the percent-encoded query component (with `+` for spaces)
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let hex = bytes
+            .get(pos + 1..pos + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[pos], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                pos += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                pos += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                pos += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Media type without parameters, such as `charset`
+fn media_type(value: &str) -> String {
+    value
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+// The quality of the most specific media range matching the content type
+fn accepted_quality(media_ranges: &[(String, f32)], content_type: &str) -> Option<f32> {
+    let any_subtype = content_type
+        .split_once('/')
+        .map(|(media_type, _)| format!("{}/*", media_type));
+
+    media_ranges
+        .iter()
+        .find(|(range, _)| range == content_type)
+        .or_else(|| {
+            media_ranges
+                .iter()
+                .find(|(range, _)| Some(range) == any_subtype.as_ref())
+        })
+        .or_else(|| media_ranges.iter().find(|(range, _)| range == "*/*"))
+        .map(|(_, quality)| *quality)
+}
+
+#[cfg(all(test, feature = "json", feature = "text"))]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use crate::{JsonArrayStreamFormat, TextStreamFormat};
+    use axum::response::IntoResponse;
+    use axum::{routing::*, Router};
+    use futures::stream;
+
+    #[tokio::test]
+    async fn negotiate_stream_format() {
+        let app = Router::new().route(
+            "/",
+            get(|negotiation: StreamFormatNegotiation| async {
+                negotiation
+                    .format("json", JsonArrayStreamFormat::new())
+                    .format("text", TextStreamFormat::new())
+                    .negotiate(stream::iter(vec![
+                        "First".to_string(),
+                        "Second".to_string(),
+                    ]))
+                    .into_response()
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .header("accept", "text/plain;q=0.5, application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.headers()["content-type"], "application/json");
+        assert_eq!(res.headers()["vary"], "accept");
+        assert_eq!(res.text().await.unwrap(), r#"["First","Second"]"#);
+
+        let res = client
+            .get("/")
+            .header("accept", "application/*;q=0.1, text/*")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.text().await.unwrap(), "FirstSecond");
+
+        let res = client.get("/?format=text").send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "FirstSecond");
+
+        let res = client
+            .get("/?foo=bar&%66ormat=%74ext")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.text().await.unwrap(), "FirstSecond");
+
+        let res = client.get("/").send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), r#"["First","Second"]"#);
+
+        let res = client
+            .get("/")
+            .header("accept", "image/png")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+    }
+
+    #[tokio::test]
+    async fn negotiate_stream_format_with_options() {
+        let app = Router::new().route(
+            "/",
+            get(|negotiation: StreamFormatNegotiation| async {
+                negotiation
+                    .format("json", JsonArrayStreamFormat::new())
+                    .format("text", TextStreamFormat::new())
+                    .options(
+                        StreamBodyAsOptions::new()
+                            .content_type(HeaderValue::from_static("application/x-test")),
+                    )
+                    .negotiate(stream::iter(vec!["First".to_string()]))
+                    .into_response()
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .header("accept", "application/x-test")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.headers()["content-type"], "application/x-test");
+        assert_eq!(res.text().await.unwrap(), r#"["First"]"#);
+
+        let res = client
+            .get("/")
+            .header("accept", "text/plain")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+    }
+
+    #[test]
+    fn parse_quality_values() {
+        assert_eq!(
+            quality_values("Text/Plain; charset=utf-8; q=0.5, application/json,, gzip;q=0"),
+            vec![
+                ("text/plain".to_string(), 0.5),
+                ("application/json".to_string(), 1.0),
+                ("gzip".to_string(), 0.0)
+            ]
+        );
+        assert_eq!(percent_decode("a%2Fb+c%zz%"), "a/b c%zz%");
+    }
+}
@@ -0,0 +1,212 @@
+use futures::stream::BoxStream;
+use futures::{Future, Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{Instant, Sleep};
+
+/// Token bucket rate limiter to pace the responses by the serialized items or bytes per second.
+/// The pacing applies to the serialized chunks produced by the formats, before the buffering and the compression.
+///
+/// The limiter is cheap to clone and the clones share the same bucket,
+/// so passing it to several responses caps their aggregate rate:
+/// ```rust,ignore
+/// let limiter = StreamRateLimiter::bytes_per_second(1024 * 1024);
+/// // In the handlers:
+/// StreamBodyAsOptions::new()
+///     .rate_limiter(limiter.clone())
+///     .csv(source_test_stream())
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamRateLimiter {
+    unit: StreamRateUnit,
+    bucket: Arc<Mutex<TokenBucket>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamRateUnit {
+    Items,
+    Bytes,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl StreamRateLimiter {
+    /// Limit the number of the serialized items per second.
+    /// Each serialized chunk costs one item, including the chunks the formats write around the items
+    /// (such as the JSON array brackets or the Arrow end of stream marker).
+    pub fn items_per_second(items: u32) -> Self {
+        Self::new(StreamRateUnit::Items, items as f64)
+    }
+
+    /// Limit the number of the serialized bytes per second (before the compression).
+    pub fn bytes_per_second(bytes: u64) -> Self {
+        Self::new(StreamRateUnit::Bytes, bytes as f64)
+    }
+
+    fn new(unit: StreamRateUnit, rate: f64) -> Self {
+        let rate = rate.max(1.0);
+        Self {
+            unit,
+            bucket: Arc::new(Mutex::new(TokenBucket {
+                rate,
+                capacity: rate,
+                tokens: rate,
+                updated_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// The number of items or bytes that can be sent at once after the idle time.
+    /// By default, it is the rate for one second.
+    pub fn with_burst(self, burst: u64) -> Self {
+        {
+            let mut bucket = self.bucket.lock().unwrap();
+            bucket.capacity = (burst as f64).max(1.0);
+            bucket.tokens = bucket.tokens.min(bucket.capacity);
+        }
+        self
+    }
+
+    // Takes the tokens for the bytes in advance (the bucket may go below zero),
+    // returning how long to wait until they are available
+    fn reserve(&self, bytes: &axum::body::Bytes) -> Duration {
+        let cost = match self.unit {
+            StreamRateUnit::Items => 1.0,
+            StreamRateUnit::Bytes => bytes.len() as f64,
+        };
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.updated_at).as_secs_f64() * bucket.rate;
+        bucket.tokens = (bucket.tokens + refill).min(bucket.capacity);
+        bucket.updated_at = now;
+        bucket.tokens -= cost;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / bucket.rate)
+        }
+    }
+}
+
+/// Holds the serialized items until the rate limiter allows to send them.
+pub(crate) struct PacedBytesStream<'a> {
+    stream: BoxStream<'a, Result<axum::body::Bytes, axum::Error>>,
+    rate_limiter: StreamRateLimiter,
+    delayed: Option<(axum::body::Bytes, Pin<Box<Sleep>>)>,
+}
+
+impl<'a> PacedBytesStream<'a> {
+    pub(crate) fn new(
+        stream: BoxStream<'a, Result<axum::body::Bytes, axum::Error>>,
+        rate_limiter: StreamRateLimiter,
+    ) -> Self {
+        Self {
+            stream,
+            rate_limiter,
+            delayed: None,
+        }
+    }
+}
+
+impl<'a> Stream for PacedBytesStream<'a> {
+    type Item = Result<axum::body::Bytes, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.delayed.is_none() {
+            match self.stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(bytes))) => {
+                    let delay = self.rate_limiter.reserve(&bytes);
+                    if delay.is_zero() {
+                        return Poll::Ready(Some(Ok(bytes)));
+                    }
+                    self.delayed = Some((bytes, Box::pin(tokio::time::sleep(delay))));
+                }
+                other => return other,
+            }
+        }
+
+        if self
+            .delayed
+            .as_mut()
+            .map_or(false, |(_, sleep)| sleep.as_mut().poll(cx).is_pending())
+        {
+            return Poll::Pending;
+        }
+        Poll::Ready(self.delayed.take().map(|(bytes, _)| Ok(bytes)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let delayed = usize::from(self.delayed.is_some());
+        let (lower, upper) = self.stream.size_hint();
+        (
+            lower.saturating_add(delayed),
+            upper.and_then(|upper| upper.checked_add(delayed)),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "text"))]
+mod tests {
+    use super::*;
+    use crate::StreamBodyAsOptions;
+    use axum::response::IntoResponse;
+    use futures::TryStreamExt;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_pacing_shared_limiter() {
+        let rate_limiter = StreamRateLimiter::items_per_second(50).with_burst(1);
+        let test_stream = || futures::stream::iter((0..3).map(|idx| idx.to_string()));
+
+        let started_at = Instant::now();
+        let (first, second) = futures::future::join(
+            StreamBodyAsOptions::new()
+                .rate_limiter(rate_limiter.clone())
+                .text(test_stream())
+                .into_response()
+                .into_body()
+                .into_data_stream()
+                .try_collect::<Vec<_>>(),
+            StreamBodyAsOptions::new()
+                .rate_limiter(rate_limiter)
+                .text(test_stream())
+                .into_response()
+                .into_body()
+                .into_data_stream()
+                .try_collect::<Vec<_>>(),
+        )
+        .await;
+
+        assert_eq!(first.unwrap().concat(), b"012");
+        assert_eq!(second.unwrap().concat(), b"012");
+        // 6 items with the burst of 1 item at 50 items per second
+        assert_eq!(started_at.elapsed(), Duration::from_millis(100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_pacing_bytes() {
+        let rate_limiter = StreamRateLimiter::bytes_per_second(100).with_burst(10);
+
+        let started_at = Instant::now();
+        let data: Vec<axum::body::Bytes> = StreamBodyAsOptions::new()
+            .rate_limiter(rate_limiter)
+            .text(futures::stream::iter(vec!["0123456789".repeat(2); 2]))
+            .into_response()
+            .into_body()
+            .into_data_stream()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(data.len(), 2);
+        // 40 bytes with the burst of 10 bytes at 100 bytes per second
+        assert_eq!(started_at.elapsed(), Duration::from_millis(300));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::stream_lifecycle::{StreamEndReason, StreamObserver, StreamSummary};
+use http::HeaderMap;
+use std::time::Duration;
+use tracing::field::Empty;
+
+/// The span covering the response body lifetime, created in the current span
+/// (usually the request span), so the stream events are correlated with the request.
+pub(crate) struct StreamTracing {
+    span: tracing::Span,
+}
+
+impl StreamTracing {
+    pub(crate) fn new(format_name: &'static str, headers: Option<&HeaderMap>) -> Self {
+        let content_type = headers
+            .and_then(|headers| headers.get(http::header::CONTENT_TYPE))
+            .and_then(|content_type| content_type.to_str().ok())
+            .unwrap_or_default();
+        let span = tracing::info_span!(
+            "axum_streams.response",
+            format = format_name,
+            content_type = content_type,
+            items = Empty,
+            bytes = Empty,
+            status = Empty,
+        );
+        Self { span }
+    }
+
+    pub(crate) fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+}
+
+impl StreamObserver for StreamTracing {
+    fn on_items(&mut self, _new_items: usize, first: bool, elapsed: Duration) {
+        if first {
+            let _entered = self.span.enter();
+            tracing::debug!(elapsed_ms = elapsed.as_millis() as u64, "First stream item");
+        }
+    }
+
+    fn on_finish(&mut self, summary: &StreamSummary) {
+        let items = summary.items;
+        let bytes = summary.bytes;
+        let elapsed_ms = summary.duration.as_millis() as u64;
+        self.span.record("items", items);
+        self.span.record("bytes", bytes);
+        self.span.record("status", summary.reason.status());
+
+        let _entered = self.span.enter();
+        match &summary.reason {
+            StreamEndReason::Cancelled => tracing::warn!(
+                items,
+                bytes,
+                elapsed_ms,
+                "Stream cancelled before completion"
+            ),
+            StreamEndReason::Error(error) => tracing::error!(
+                items,
+                bytes,
+                elapsed_ms,
+                error = error.as_str(),
+                "Stream ended with error"
+            ),
+            StreamEndReason::Complete => {
+                tracing::info!(items, bytes, elapsed_ms, "Stream complete")
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "text"))]
+mod tests {
+    use crate::StreamBodyAs;
+    use axum::response::IntoResponse;
+    use futures::StreamExt;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct TestWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for TestWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stream_tracing() {
+        let writer = TestWriter::default();
+        let output = writer.0.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let stream_body_as = StreamBodyAs::text_with_errors(futures::stream::iter(vec![
+                Ok("First".to_string()),
+                Err(axum::Error::new("test error")),
+            ]));
+            let body = stream_body_as
+                .into_response()
+                .into_body()
+                .into_data_stream();
+            futures::executor::block_on(body.collect::<Vec<_>>());
+        });
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines.iter().all(|line| line.contains(
+            "axum_streams.response{format=\"text\" content_type=\"text/plain; charset=utf-8\""
+        )));
+        assert!(lines[0].contains("First stream item"));
+        assert!(lines[1].contains("Stream item error") && lines[1].contains("test error"));
+        assert!(
+            lines[2].contains("Stream ended with error") && lines[2].contains("items=1 bytes=5")
+        );
+    }
+}
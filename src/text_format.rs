@@ -41,6 +41,14 @@ impl StreamingFormat<String> for TextStreamFormat {
         );
         Some(header_map)
     }
+
+    fn format_name(&self) -> &'static str {
+        "text"
+    }
+
+    fn heartbeat_bytes(&self) -> Option<axum::body::Bytes> {
+        Some(axum::body::Bytes::from_static(b"\n"))
+    }
 }
 
 impl<'a> StreamBodyAs<'a> {